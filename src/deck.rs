@@ -0,0 +1,425 @@
+pub mod error;
+pub mod storage;
+
+pub use self::error::DeckError;
+pub use self::storage::DeckFormat;
+
+use crate::flashcard::{
+    AppearanceReference, FlashCard, Language, PartOfSpeech, TvSeriesReference, WordFrequency,
+};
+use crate::subtitle::Subtitle;
+use crate::words::{FrequencyList, FrequencyLists, Lemmatizer, Tokenizer, WordIndex};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+lazy_static! {
+    //S01E01 in names like "papel_S01E01_es". \d would also match digits of other scripts.
+    static ref EPISODE_REGEX: Regex = Regex::new(r#"(?i)S([0-9]{1,3})E([0-9]{1,4})"#).unwrap();
+    static ref SEASON_REGEX: Regex = Regex::new(r#"(?i)(?:^|[^a-z])S([0-9]{1,3})(?:[^a-z0-9]|$)"#).unwrap();
+}
+
+///Which words of a subtitle become cards
+pub struct DeckOptions {
+    ///frequency bands of the words to keep. All words are kept if it is empty.
+    pub frequencies: Vec<WordFrequency>,
+    ///lemmas and forms which never become cards. Compared in lowercase.
+    pub stop_words: HashSet<String>,
+    ///lemmas the learner knows already
+    pub known_words: HashSet<String>,
+    ///parts of speech which never become cards, by default the function words
+    pub skipped_parts_of_speech: Vec<PartOfSpeech>,
+    ///maximum number of example sentences of a card
+    pub max_examples: usize,
+    ///true to make cards of the words of the songs as well, see `Subtitle::lyrics`
+    pub lyrics: bool,
+}
+
+impl DeckOptions {
+    pub fn new() -> DeckOptions {
+        DeckOptions {
+            frequencies: vec![],
+            stop_words: HashSet::new(),
+            known_words: HashSet::new(),
+            skipped_parts_of_speech: vec![
+                PartOfSpeech::Determiner,
+                PartOfSpeech::Adposition,
+                PartOfSpeech::Conjunction,
+                PartOfSpeech::Pronoun,
+                PartOfSpeech::Numeral,
+            ],
+            max_examples: 3,
+            lyrics: false,
+        }
+    }
+
+    fn skips(&self, lemma: &str, form: &str, part_of_speech: PartOfSpeech) -> bool {
+        self.skipped_parts_of_speech.contains(&part_of_speech)
+            || self.stop_words.contains(lemma)
+            || self.stop_words.contains(form)
+            || self.known_words.contains(lemma)
+    }
+}
+
+impl Default for DeckOptions {
+    fn default() -> Self {
+        DeckOptions::new()
+    }
+}
+
+///Flash cards of one language
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Deck {
+    ///version of the file format, see `storage::VERSION`
+    pub version: u64,
+    pub language: Language,
+    ///id of the next card added. Ids of removed cards aren't used again.
+    pub next_id: u64,
+    pub cards: Vec<FlashCard>,
+}
+
+impl Deck {
+    pub fn new(language: Language) -> Deck {
+        Deck {
+            version: storage::VERSION,
+            language,
+            next_id: 1,
+            cards: vec![],
+        }
+    }
+
+    ///Adds the card with a new id and returns the id
+    pub fn add(&mut self, mut card: FlashCard) -> u64 {
+        card.id = self.next_id;
+        self.next_id += 1;
+        self.cards.push(card);
+        self.next_id - 1
+    }
+
+    ///Adds the cards of a deck created from another subtitle. Cards of lemmas the deck has already
+    ///keep their id and progress and get the new forms, examples and appearances.
+    ///Lemmas with the same word but another part of speech get their own card.
+    pub fn merge(&mut self, other: Deck) {
+        for card in other.cards {
+            match self.position(&card.word, card.part_of_speech) {
+                Some(i) => {
+                    let existing = &mut self.cards[i];
+                    if existing.part_of_speech == PartOfSpeech::Undefined {
+                        existing.part_of_speech = card.part_of_speech;
+                    }
+                    for form in card.forms.iter() {
+                        existing.add_form(form);
+                    }
+                    for sentence in card.example_sentence {
+                        if !existing.example_sentence.contains(&sentence) {
+                            existing.example_sentence.push(sentence);
+                        }
+                    }
+                    for reference in card.used_in {
+                        if !existing.used_in.contains(&reference) {
+                            existing.used_in.push(reference);
+                        }
+                    }
+                }
+                None => {
+                    self.add(card);
+                }
+            }
+        }
+    }
+
+    ///The card with the id
+    pub fn card(&self, id: u64) -> Option<&FlashCard> {
+        self.cards.iter().find(|card| card.id == id)
+    }
+
+    ///Creates a card for each word of the subtitle with the default options, the lexicon and the frequency list
+    ///shipped for the language. See `Deck::from_subtitle_with`.
+    pub fn from_subtitle(subtitle: &Subtitle, language: Language) -> Result<Deck, DeckError> {
+        let lemmatizer = Lemmatizer::for_language(language)?;
+        let mut lists = FrequencyLists::new();
+        if language == Language::Spanish {
+            lists.insert(FrequencyList::spanish()?);
+        }
+        Ok(Deck::from_subtitle_with(
+            subtitle,
+            &lemmatizer,
+            &lists,
+            &DeckOptions::new(),
+        ))
+    }
+
+    ///Creates a card for each lemma of the subtitle in the order of their first appearance.
+    ///A card has the forms seen in the subtitle, the first sentences it appears in as examples
+    ///and a reference to the second of each appearance with the sentence as clip.
+    pub fn from_subtitle_with(
+        subtitle: &Subtitle,
+        lemmatizer: &Lemmatizer,
+        lists: &FrequencyLists,
+        options: &DeckOptions,
+    ) -> Deck {
+        let language = lemmatizer.language;
+        let tokenizer = Tokenizer::new(language);
+        let index = WordIndex::from_subtitle(subtitle, &tokenizer);
+        let (is_movie, tv_series_reference) = series_reference(&subtitle.name);
+        let mut deck = Deck::new(language);
+        //the lyrics follow the sentences, so the indices of the sentences still match the word index
+        let lyrics = if options.lyrics {
+            subtitle.lyrics()
+        } else {
+            vec![]
+        };
+        for (i, sentence) in subtitle.sentences.iter().chain(lyrics.iter()).enumerate() {
+            for token in tokenizer.tokenize(&sentence.sentence) {
+                let occurrence = index
+                    .occurrences(&token.normalized)
+                    .iter()
+                    .find(|o| o.sentence == i && o.form == token.text);
+                let second = occurrence.map_or(sentence.start, |o| o.time).as_secs() as u32;
+                for lemma in lemmatizer.lemmatize_token(&token) {
+                    if options.skips(&lemma.lemma, &token.normalized, lemma.part_of_speech) {
+                        continue;
+                    }
+                    let card = deck.card_mut(&lemma.lemma, lemma.part_of_speech);
+                    card.add_form(&token.normalized);
+                    if card.example_sentence.len() < options.max_examples
+                        && !card.example_sentence.contains(&sentence.sentence)
+                    {
+                        card.example_sentence.push(sentence.sentence.clone());
+                    }
+                    let seen = card.used_in.iter().any(|r| {
+                        r.name == subtitle.name
+                            && r.appearance_at_second == second
+                            && r.clip_start == sentence.start
+                    });
+                    if !seen {
+                        card.used_in.push(AppearanceReference {
+                            name: subtitle.name.clone(),
+                            is_movie,
+                            tv_series_reference: tv_series_reference.clone(),
+                            appearance_at_second: second,
+                            clip_start: sentence.start,
+                            clip_end: sentence.end,
+                        });
+                    }
+                }
+            }
+        }
+        for card in deck.cards.iter_mut() {
+            card.frequency = frequency(lists, card);
+        }
+        if !options.frequencies.is_empty() {
+            deck.cards
+                .retain(|card| options.frequencies.contains(&card.frequency));
+        }
+        deck
+    }
+
+    ///The card of the lemma
+    pub fn get(&self, word: &str) -> Option<&FlashCard> {
+        self.cards.iter().find(|card| card.word == word)
+    }
+
+    ///The card of the lemma, which is added if the deck doesn't have it yet.
+    ///The part of speech is set if the card doesn't have one, as the lemmatizer may only know it for some forms.
+    fn card_mut(&mut self, word: &str, part_of_speech: PartOfSpeech) -> &mut FlashCard {
+        let i = match self.position(word, part_of_speech) {
+            Some(i) => {
+                if self.cards[i].part_of_speech == PartOfSpeech::Undefined {
+                    self.cards[i].part_of_speech = part_of_speech;
+                }
+                i
+            }
+            None => {
+                let mut card = FlashCard::new(String::from(word), self.language);
+                card.part_of_speech = part_of_speech;
+                self.add(card);
+                self.cards.len() - 1
+            }
+        };
+        &mut self.cards[i]
+    }
+
+    ///The position of the card of the lemma. A card or lemma without part of speech matches any part of speech.
+    fn position(&self, word: &str, part_of_speech: PartOfSpeech) -> Option<usize> {
+        self.cards
+            .iter()
+            .position(|card| card.word == word && card.part_of_speech == part_of_speech)
+            .or_else(|| {
+                self.cards.iter().position(|card| {
+                    card.word == word
+                        && (card.part_of_speech == PartOfSpeech::Undefined
+                            || part_of_speech == PartOfSpeech::Undefined)
+                })
+            })
+    }
+
+    pub fn len(&self) -> usize {
+        self.cards.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cards.is_empty()
+    }
+}
+
+///The band of the most frequent of the lemma and the forms of the card, as lists like the CREA list count forms
+fn frequency(lists: &FrequencyLists, card: &FlashCard) -> WordFrequency {
+    let list = match lists.get(card.language) {
+        Some(list) => list,
+        None => return WordFrequency::Undefined,
+    };
+    let rank = std::iter::once(&card.word)
+        .chain(card.forms.iter())
+        .filter_map(|word| list.rank(word))
+        .min();
+    lists.bands.classify(rank)
+}
+
+///Reads the season and episode from names like "papel_S01E01_es". Names without them are taken for movies.
+fn series_reference(name: &str) -> (bool, Option<TvSeriesReference>) {
+    if let Some(caps) = EPISODE_REGEX.captures(name) {
+        if let (Ok(season), Ok(episode)) = (caps[1].parse(), caps[2].parse()) {
+            return (
+                false,
+                Some(TvSeriesReference::SeasonEpisode(season, episode)),
+            );
+        }
+    }
+    match SEASON_REGEX
+        .captures(name)
+        .and_then(|caps| caps[1].parse().ok())
+    {
+        Some(season) => (false, Some(TvSeriesReference::Season(season))),
+        None => (true, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flashcard::ProficiencyLevel;
+    use crate::subtitle::ParseMode;
+    use std::time::Duration;
+
+    fn subtitle() -> Subtitle {
+        let srt = "1\n00:00:01,000 --> 00:00:03,500\n- ¿Tomaste el dinero?\n- Sí, tómalo tú.\n\n\
+                   2\n00:00:10,000 --> 00:00:12,000\nTomé el dinero del banco\n\n\
+                   3\n00:00:12,500 --> 00:00:14,000\ny los rehenes.\n";
+        Subtitle::from_srt_string("papel_S01E02_es", srt, ParseMode::Strict).unwrap()
+    }
+
+    #[test]
+    fn test_from_subtitle() {
+        let deck = Deck::from_subtitle(&subtitle(), Language::Spanish).unwrap();
+        let words: Vec<&str> = deck.cards.iter().map(|c| c.word.as_str()).collect();
+        assert_eq!(words, vec!["tomar", "dinero", "sí", "banco", "rehén"]);
+        let tomar = deck.get("tomar").unwrap();
+        assert_eq!(tomar.part_of_speech, PartOfSpeech::Verb);
+        assert_eq!(tomar.forms, vec!["tomaste", "tómalo", "tomé"]);
+        assert_eq!(
+            tomar.example_sentence,
+            vec![
+                "¿Tomaste el dinero?",
+                "Sí, tómalo tú.",
+                "Tomé el dinero del banco y los rehenes."
+            ]
+        );
+        assert_eq!(tomar.frequency, WordFrequency::High);
+        let rehenes = &deck.get("rehén").unwrap().used_in[0];
+        assert_eq!(rehenes.appearance_at_second, 12);
+        assert_eq!(rehenes.clip_start, Duration::from_secs(10));
+        assert_eq!(rehenes.clip_end, Duration::from_secs(14));
+        assert!(!rehenes.is_movie);
+        assert_eq!(
+            rehenes.tv_series_reference,
+            Some(TvSeriesReference::SeasonEpisode(1, 2))
+        );
+    }
+
+    #[test]
+    fn test_filters() {
+        let lemmatizer = Lemmatizer::spanish().unwrap();
+        let mut lists = FrequencyLists::new();
+        lists.insert(FrequencyList::spanish().unwrap());
+        let mut options = DeckOptions::new();
+        options.known_words.insert(String::from("tomar"));
+        options.stop_words.insert(String::from("sí"));
+        let deck = Deck::from_subtitle_with(&subtitle(), &lemmatizer, &lists, &options);
+        let words: Vec<&str> = deck.cards.iter().map(|c| c.word.as_str()).collect();
+        assert_eq!(words, vec!["dinero", "banco", "rehén"]);
+        options.frequencies = vec![WordFrequency::Low, WordFrequency::VeryLow];
+        let deck = Deck::from_subtitle_with(&subtitle(), &lemmatizer, &lists, &options);
+        assert!(deck.get("dinero").is_none());
+        assert!(deck.get("rehén").is_some());
+    }
+
+    #[test]
+    fn test_lyrics() {
+        let srt = "1\n00:00:01,000 --> 00:00:03,000\n♪ Una mañana me he despertado ♪\n\n\
+                   2\n00:00:04,000 --> 00:00:05,000\nEl dinero.\n";
+        let subtitle =
+            Subtitle::from_srt_string("papel_S01E03_es", srt, ParseMode::Strict).unwrap();
+        let lemmatizer = Lemmatizer::spanish().unwrap();
+        let lists = FrequencyLists::new();
+        let mut options = DeckOptions::new();
+        let deck = Deck::from_subtitle_with(&subtitle, &lemmatizer, &lists, &options);
+        assert!(deck.get("mañana").is_none());
+        options.lyrics = true;
+        let deck = Deck::from_subtitle_with(&subtitle, &lemmatizer, &lists, &options);
+        let card = deck.get("mañana").unwrap();
+        assert_eq!(card.example_sentence, vec!["Una mañana me he despertado"]);
+        assert_eq!(card.used_in[0].clip_start, Duration::from_secs(1));
+        assert!(deck.get("dinero").is_some());
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut deck = Deck::new(Language::Spanish);
+        let mut card = FlashCard::new(String::from("dinero"), Language::Spanish);
+        card.proficiency = ProficiencyLevel::LevelFour;
+        deck.add(card);
+        deck.merge(Deck::from_subtitle(&subtitle(), Language::Spanish).unwrap());
+        let dinero = deck.get("dinero").unwrap();
+        assert_eq!(dinero.id, 1);
+        assert_eq!(dinero.proficiency, ProficiencyLevel::LevelFour);
+        assert_eq!(dinero.used_in.len(), 2);
+        assert_eq!(deck.get("tomar").unwrap().id, 2);
+        assert_eq!(deck.next_id, 6);
+        assert_eq!(dinero.part_of_speech, PartOfSpeech::Noun);
+    }
+
+    #[test]
+    fn test_merge_parts_of_speech() {
+        let mut deck = Deck::new(Language::Spanish);
+        let mut card = FlashCard::new(String::from("bajo"), Language::Spanish);
+        card.part_of_speech = PartOfSpeech::Adjective;
+        card.proficiency = ProficiencyLevel::LevelThree;
+        deck.add(card);
+        let mut other = Deck::new(Language::Spanish);
+        let mut card = FlashCard::new(String::from("bajo"), Language::Spanish);
+        card.part_of_speech = PartOfSpeech::Adposition;
+        card.add_form("bajo");
+        other.add(card);
+        deck.merge(other);
+        assert_eq!(deck.len(), 2);
+        assert_eq!(deck.cards[0].proficiency, ProficiencyLevel::LevelThree);
+        assert!(deck.cards[0].forms.is_empty());
+        assert_eq!(deck.cards[1].part_of_speech, PartOfSpeech::Adposition);
+        assert_eq!(deck.cards[1].proficiency, ProficiencyLevel::LevelOne);
+    }
+
+    #[test]
+    fn test_series_reference() {
+        assert_eq!(series_reference("Movie"), (true, None));
+        assert_ne!(
+            series_reference("papel_S01E02_es"),
+            series_reference("papel_S02E02_es")
+        );
+        assert_eq!(series_reference("papel_S٠١E٠٢_es"), (true, None));
+        assert_eq!(
+            series_reference("show.s02.es"),
+            (false, Some(TvSeriesReference::Season(2)))
+        );
+    }
+}
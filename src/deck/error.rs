@@ -0,0 +1,89 @@
+use crate::words::{FrequencyListError, LexiconError};
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+///Errors which can occur while creating, reading or writing a deck
+#[derive(Debug)]
+pub enum DeckError {
+    ///The lexicon of the lemmatizer could not be read
+    Lexicon(LexiconError),
+    ///The frequency list of the language could not be read
+    FrequencyList(FrequencyListError),
+    ///The deck file could not be read or written
+    Io(io::Error),
+    ///The deck file is not valid JSON or doesn't describe a deck
+    Json(serde_json::Error),
+    ///The deck file is not valid TOML
+    TomlRead(toml::de::Error),
+    ///The deck could not be written as TOML
+    TomlWrite(toml::ser::Error),
+    ///The deck was written by a newer version of the program
+    UnsupportedVersion { version: u64 },
+}
+
+impl fmt::Display for DeckError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DeckError::Lexicon(e) => write!(f, "{}", e),
+            DeckError::FrequencyList(e) => write!(f, "{}", e),
+            DeckError::Io(e) => write!(f, "could not access deck file: {}", e),
+            DeckError::Json(e) => write!(f, "invalid deck: {}", e),
+            DeckError::TomlRead(e) => write!(f, "invalid deck: {}", e),
+            DeckError::TomlWrite(e) => write!(f, "could not write deck: {}", e),
+            DeckError::UnsupportedVersion { version } => {
+                write!(f, "deck version {} is not supported", version)
+            }
+        }
+    }
+}
+
+impl Error for DeckError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DeckError::Lexicon(e) => Some(e),
+            DeckError::FrequencyList(e) => Some(e),
+            DeckError::Io(e) => Some(e),
+            DeckError::Json(e) => Some(e),
+            DeckError::TomlRead(e) => Some(e),
+            DeckError::TomlWrite(e) => Some(e),
+            DeckError::UnsupportedVersion { .. } => None,
+        }
+    }
+}
+
+impl From<LexiconError> for DeckError {
+    fn from(e: LexiconError) -> DeckError {
+        DeckError::Lexicon(e)
+    }
+}
+
+impl From<FrequencyListError> for DeckError {
+    fn from(e: FrequencyListError) -> DeckError {
+        DeckError::FrequencyList(e)
+    }
+}
+
+impl From<io::Error> for DeckError {
+    fn from(e: io::Error) -> DeckError {
+        DeckError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for DeckError {
+    fn from(e: serde_json::Error) -> DeckError {
+        DeckError::Json(e)
+    }
+}
+
+impl From<toml::de::Error> for DeckError {
+    fn from(e: toml::de::Error) -> DeckError {
+        DeckError::TomlRead(e)
+    }
+}
+
+impl From<toml::ser::Error> for DeckError {
+    fn from(e: toml::ser::Error) -> DeckError {
+        DeckError::TomlWrite(e)
+    }
+}
@@ -0,0 +1,224 @@
+use super::{Deck, DeckError};
+use serde_json::Value;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+///Version of the deck file format written by this program
+pub const VERSION: u64 = 1;
+
+///Migrations of the deck file format. The migration at index `i` turns version `i` into version `i + 1`.
+const MIGRATIONS: [fn(&mut Value); VERSION as usize] = [migrate_v0];
+
+///Format of a deck file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeckFormat {
+    Json,
+    Toml,
+}
+
+impl DeckFormat {
+    ///TOML for the extension `.toml`, otherwise JSON
+    pub fn from_path(path: &str) -> DeckFormat {
+        match Path::new(path).extension().and_then(|e| e.to_str()) {
+            Some(extension) if extension.eq_ignore_ascii_case("toml") => DeckFormat::Toml,
+            _ => DeckFormat::Json,
+        }
+    }
+}
+
+impl Deck {
+    ///Reads a deck written by `Deck::save` or by an older version of the program.
+    ///The format is chosen by the file extension, see `DeckFormat::from_path`.
+    pub fn load(path: &str) -> Result<Deck, DeckError> {
+        let contents = fs::read_to_string(path)?;
+        Deck::from_string(&contents, DeckFormat::from_path(path))
+    }
+
+    ///Writes the deck to a temporary file next to the destination and renames it,
+    ///so the file keeps its old content if the program is interrupted while writing
+    pub fn save(&self, path: &str) -> Result<(), DeckError> {
+        let contents = self.to_string(DeckFormat::from_path(path))?;
+        write_atomically(Path::new(path), contents.as_bytes())?;
+        Ok(())
+    }
+
+    pub fn from_string(contents: &str, format: DeckFormat) -> Result<Deck, DeckError> {
+        let mut value: Value = match format {
+            DeckFormat::Json => serde_json::from_str(contents)?,
+            DeckFormat::Toml => toml::from_str(contents)?,
+        };
+        migrate(&mut value)?;
+        Ok(serde_json::from_value(value)?)
+    }
+
+    pub fn to_string(&self, format: DeckFormat) -> Result<String, DeckError> {
+        match format {
+            DeckFormat::Json => Ok(serde_json::to_string_pretty(self)?),
+            DeckFormat::Toml => {
+                //a value puts the fields of each table before its subtables, which TOML requires
+                let value = to_toml(serde_json::to_value(self)?)
+                    .unwrap_or_else(|| toml::Value::Table(toml::value::Table::new()));
+                Ok(toml::to_string(&value)?)
+            }
+        }
+    }
+}
+
+///Converts JSON to TOML, which has neither null nor variants with data like `Episode(1)`.
+///Fields which are null are left out and variants become tables like `{Episode = 1}`, as in JSON.
+fn to_toml(value: Value) -> Option<toml::Value> {
+    match value {
+        Value::Null => None,
+        Value::Bool(b) => Some(toml::Value::Boolean(b)),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => Some(toml::Value::Integer(i)),
+            None => n.as_f64().map(toml::Value::Float),
+        },
+        Value::String(s) => Some(toml::Value::String(s)),
+        Value::Array(values) => Some(toml::Value::Array(
+            values.into_iter().filter_map(to_toml).collect(),
+        )),
+        Value::Object(fields) => Some(toml::Value::Table(
+            fields
+                .into_iter()
+                .filter_map(|(key, value)| to_toml(value).map(|value| (key, value)))
+                .collect(),
+        )),
+    }
+}
+
+///Brings a deck of any older version to the current version. Decks without version are version 0.
+fn migrate(value: &mut Value) -> Result<(), DeckError> {
+    let version = value.get("version").and_then(Value::as_u64).unwrap_or(0);
+    if version > VERSION {
+        return Err(DeckError::UnsupportedVersion { version });
+    }
+    for migration in MIGRATIONS[version as usize..].iter() {
+        migration(value);
+    }
+    if let Some(deck) = value.as_object_mut() {
+        deck.insert(String::from("version"), Value::from(VERSION));
+    }
+    Ok(())
+}
+
+///Version 0 decks have no card ids
+fn migrate_v0(value: &mut Value) {
+    let mut next_id = 1;
+    if let Some(cards) = value.get_mut("cards").and_then(Value::as_array_mut) {
+        for card in cards.iter_mut().filter_map(Value::as_object_mut) {
+            card.insert(String::from("id"), Value::from(next_id));
+            next_id += 1;
+        }
+    }
+    if let Some(deck) = value.as_object_mut() {
+        deck.insert(String::from("next_id"), Value::from(next_id));
+    }
+}
+
+fn write_atomically(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let file_name = path.file_name().map_or_else(
+        || String::from("deck"),
+        |name| name.to_string_lossy().into_owned(),
+    );
+    let temporary: PathBuf = path.with_file_name(format!(".{}.tmp", file_name));
+    let result = File::create(&temporary).and_then(|mut file| {
+        file.write_all(contents)?;
+        //the data has to be on the disk before the rename makes it the deck
+        file.sync_all()
+    });
+    match result.and_then(|_| fs::rename(&temporary, path)) {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            let _ = fs::remove_file(&temporary);
+            Err(e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flashcard::{
+        AppearanceReference, FlashCard, Language, ProficiencyLevel, TvSeriesReference,
+    };
+    use std::time::Duration;
+
+    fn deck() -> Deck {
+        let mut deck = Deck::new(Language::Spanish);
+        let mut card = FlashCard::new(String::from("tomar"), Language::Spanish);
+        card.add_form("tomé");
+        card.example_sentence.push(String::from("Tomé el dinero."));
+        card.used_in.push(AppearanceReference {
+            name: String::from("papel_S01E01_es"),
+            is_movie: false,
+            tv_series_reference: Some(TvSeriesReference::SeasonEpisode(1, 1)),
+            appearance_at_second: 10,
+            clip_start: Duration::from_millis(10500),
+            clip_end: Duration::from_secs(12),
+        });
+        card.proficiency = ProficiencyLevel::LevelThree;
+        deck.add(card);
+        deck.add(FlashCard::new(String::from("banco"), Language::Spanish));
+        deck
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for format in [DeckFormat::Json, DeckFormat::Toml] {
+            let text = deck().to_string(format).unwrap();
+            let read = Deck::from_string(&text, format).unwrap();
+            assert_eq!(read.to_string(format).unwrap(), text);
+            assert_eq!(read.next_id, 3);
+            let tomar = read.card(1).unwrap();
+            assert_eq!(tomar.word, "tomar");
+            assert_eq!(tomar.proficiency, ProficiencyLevel::LevelThree);
+            assert_eq!(tomar.used_in[0].clip_start, Duration::from_millis(10500));
+            assert_eq!(
+                tomar.used_in[0].tv_series_reference,
+                Some(TvSeriesReference::SeasonEpisode(1, 1))
+            );
+        }
+    }
+
+    #[test]
+    fn test_migration() {
+        let v0 = r#"{"language": "Spanish", "cards": [
+            {"word": "tomar", "language": "Spanish", "translation": [], "translation_language": null,
+             "example_sentence": [], "used_in": [], "frequency": "High", "proficiency": "LevelTwo"},
+            {"word": "banco", "language": "Spanish", "translation": [], "translation_language": null,
+             "example_sentence": [], "used_in": [], "frequency": "Undefined", "proficiency": "LevelOne"}
+        ]}"#;
+        let deck = Deck::from_string(v0, DeckFormat::Json).unwrap();
+        assert_eq!(deck.version, VERSION);
+        assert_eq!(deck.card(2).unwrap().word, "banco");
+        assert_eq!(deck.next_id, 3);
+        let future = r#"{"version": 99, "language": "Spanish", "next_id": 1, "cards": []}"#;
+        assert!(matches!(
+            Deck::from_string(future, DeckFormat::Json),
+            Err(DeckError::UnsupportedVersion { version: 99 })
+        ));
+    }
+
+    #[test]
+    fn test_save() {
+        let directory = std::env::temp_dir().join(format!("deck_test_{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        for name in ["deck.json", "deck.toml"] {
+            let path = directory.join(name);
+            let path = path.to_str().unwrap();
+            deck().save(path).unwrap();
+            let mut read = Deck::load(path).unwrap();
+            read.add(FlashCard::new(String::from("coche"), Language::Spanish));
+            read.save(path).unwrap();
+            assert_eq!(Deck::load(path).unwrap().card(3).unwrap().word, "coche");
+        }
+        let files: Vec<String> = fs::read_dir(&directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(files.len(), 2);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -1,5 +1,8 @@
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime};
+
 ///Enum which describes the language of the contained String
-#[derive(PartialEq, Debug)]
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Copy, Debug)]
 pub enum Language {
     English,
     German,
@@ -7,14 +10,16 @@ pub enum Language {
 }
 
 ///Reference (Season and Episode) of a TV Series Episode
-#[derive(PartialEq, Debug)]
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
 pub enum TvSeriesReference {
     Season(u32),
     Episode(u32),
+    ///season and episode, e.g. from "S01E02"
+    SeasonEpisode(u32, u32),
 }
 
 ///Frequency of the word in the corresponding language based on wordlists
-#[derive(PartialEq, Debug)]
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
 pub enum WordFrequency {
     VeryHigh,
     High,
@@ -24,28 +29,131 @@ pub enum WordFrequency {
     Undefined,
 }
 
+///Part of speech of a word, following the Universal Dependencies tags
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Copy, Debug, Default)]
+pub enum PartOfSpeech {
+    Noun,
+    ProperNoun,
+    Verb,
+    Adjective,
+    Adverb,
+    Pronoun,
+    Determiner,
+    ///prepositions like "de" or "para"
+    Adposition,
+    Conjunction,
+    Interjection,
+    Numeral,
+    #[default]
+    Undefined,
+}
+
+impl PartOfSpeech {
+    ///Reads a Universal Dependencies tag like `NOUN` or `VERB`. Auxiliaries are verbs.
+    pub fn from_tag(tag: &str) -> Option<PartOfSpeech> {
+        match tag {
+            "NOUN" => Some(PartOfSpeech::Noun),
+            "PROPN" => Some(PartOfSpeech::ProperNoun),
+            "VERB" | "AUX" => Some(PartOfSpeech::Verb),
+            "ADJ" => Some(PartOfSpeech::Adjective),
+            "ADV" => Some(PartOfSpeech::Adverb),
+            "PRON" => Some(PartOfSpeech::Pronoun),
+            "DET" => Some(PartOfSpeech::Determiner),
+            "ADP" => Some(PartOfSpeech::Adposition),
+            "CCONJ" | "SCONJ" => Some(PartOfSpeech::Conjunction),
+            "INTJ" => Some(PartOfSpeech::Interjection),
+            "NUM" => Some(PartOfSpeech::Numeral),
+            _ => None,
+        }
+    }
+}
+
 ///Proficiency Level of the word according to a 5 level Leitner system. LevelOne is the bucket for no knowledge.
-#[derive(PartialEq, Debug)]
+///The other schedulers derive it from the interval until the next review, see `proficiency_for_interval`.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug, Default)]
 pub enum ProficiencyLevel {
+    #[default]
     LevelOne,
     LevelTwo,
     LevelThree,
     LevelFour,
     LevelFive,
 }
+
+impl ProficiencyLevel {
+    pub const ALL: [ProficiencyLevel; 5] = [
+        ProficiencyLevel::LevelOne,
+        ProficiencyLevel::LevelTwo,
+        ProficiencyLevel::LevelThree,
+        ProficiencyLevel::LevelFour,
+        ProficiencyLevel::LevelFive,
+    ];
+
+    ///Position of the level in `ProficiencyLevel::ALL`, 0 for `LevelOne`
+    pub fn index(self) -> usize {
+        ProficiencyLevel::ALL
+            .iter()
+            .position(|level| *level == self)
+            .unwrap()
+    }
+
+    ///The next higher level. `LevelFive` stays.
+    pub fn next(self) -> ProficiencyLevel {
+        ProficiencyLevel::ALL[(self.index() + 1).min(ProficiencyLevel::ALL.len() - 1)]
+    }
+}
+///Answer of the learner to a card, like the four buttons of most flash card programs
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Grade {
+    ///the answer was wrong
+    Again,
+    ///the answer was right but hard to recall
+    Hard,
+    Good,
+    Easy,
+}
+
+impl Grade {
+    pub fn is_correct(self) -> bool {
+        self != Grade::Again
+    }
+}
+
+///An answer to a card and when it was given
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+pub struct Review {
+    pub time: SystemTime,
+    pub grade: Grade,
+}
+
 ///A struct describing the context of appearance of the corresponding word in a movie or tv series
-#[derive(Debug)]
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
 pub struct AppearanceReference {
     pub name: String,
     pub is_movie: bool,
     pub tv_series_reference: Option<TvSeriesReference>,
     pub appearance_at_second: u32,
+    ///start of the sentence the word appears in, to play it as a clip
+    #[serde(default)]
+    pub clip_start: Duration,
+    ///end of the sentence the word appears in
+    #[serde(default)]
+    pub clip_end: Duration,
 }
 
 ///Struct which describes the content of a TV Language Trainer Flash Card
-#[derive(Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct FlashCard {
+    ///identifies the card within its deck, assigned by `Deck::add`. 0 for cards which aren't in a deck.
+    #[serde(default)]
+    pub id: u64,
+    ///the lemma, e.g. "tomar" for "tomé" or "tómalo"
     pub word: String,
+    #[serde(default)]
+    pub part_of_speech: PartOfSpeech,
+    ///the forms of the word seen in the subtitles
+    #[serde(default)]
+    pub forms: Vec<String>,
     pub language: Language,
     pub translation: Vec<String>,
     pub translation_language: Option<Language>,
@@ -53,12 +161,26 @@ pub struct FlashCard {
     pub used_in: Vec<AppearanceReference>,
     pub frequency: WordFrequency,
     pub proficiency: ProficiencyLevel,
+    ///time of the last review, None if the card was never reviewed
+    #[serde(default)]
+    pub last_review: Option<SystemTime>,
+    ///the answers to the card, the oldest first. The schedulers derive their state from them,
+    ///so a deck can switch between scheduling algorithms.
+    #[serde(default)]
+    pub reviews: Vec<Review>,
+    ///the proficiency before the first review, e.g. of cards from decks without review history
+    #[serde(default)]
+    pub initial_proficiency: ProficiencyLevel,
 }
 
 impl FlashCard {
+    #[allow(clippy::redundant_field_names)]
     pub fn new(word: String, language: Language) -> FlashCard {
         FlashCard {
+            id: 0,
             word: word,
+            part_of_speech: PartOfSpeech::Undefined,
+            forms: vec![],
             language: language,
             translation: vec![],
             translation_language: None,
@@ -66,6 +188,16 @@ impl FlashCard {
             used_in: vec![],
             frequency: WordFrequency::Undefined,
             proficiency: ProficiencyLevel::LevelOne,
+            last_review: None,
+            reviews: vec![],
+            initial_proficiency: ProficiencyLevel::LevelOne,
+        }
+    }
+
+    ///Adds a form of the word unless the card has it already
+    pub fn add_form(&mut self, form: &str) {
+        if !self.forms.iter().any(|f| f == form) {
+            self.forms.push(String::from(form));
         }
     }
 }
@@ -85,6 +217,11 @@ mod tests {
         assert_eq!(card.used_in.len(), 0);
         assert_eq!(card.frequency, WordFrequency::Undefined);
         assert_eq!(card.proficiency, ProficiencyLevel::LevelOne);
+        assert_eq!(card.part_of_speech, PartOfSpeech::Undefined);
+        card.add_form("tomé");
+        card.add_form("tomaba");
+        card.add_form("tomé");
+        assert_eq!(card.forms, vec!["tomé", "tomaba"]);
         card.translation.push(String::from("trinken"));
         card.translation_language = Some(Language::German);
     }
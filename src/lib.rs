@@ -1,5 +1,8 @@
 #[macro_use]
 extern crate lazy_static;
+pub mod deck;
 pub mod flashcard;
+pub mod scheduler;
 pub mod subtitle;
 pub mod toolbox;
+pub mod words;
@@ -15,18 +15,19 @@ fn main() {
 
     // let mut section = SubtitleSection::new();
     // section.text = String::from(content);
-    let test_es =
-        Subtitle::from_file("Papel01", "./resources/Subtitles/papel_S01E01_es.srt").unwrap();
-    let test_en =
-        Subtitle::from_file("Papel01", "./resources/Subtitles/papel_S01E01_en.srt").unwrap();
-    for (key, sentences) in test_es.sentences {
-        for sentence in sentences {
-            println!("{}: {}", key, sentence.sentence);
+    let test_es = Subtitle::load("./resources/Subtitles/papel_S01E01_es.srt").unwrap();
+    let mut test_en = Subtitle::load("./resources/Subtitles/papel_S01E01_en.srt").unwrap();
+    //tracks from different releases are brought in sync before the alignment
+    if let Some(estimate) = sync::estimate(&test_es, &test_en, &SyncOptions::new()) {
+        test_en.retime(&estimate.mapping);
+    }
+    let alignment = Alignment::align(&test_es, &test_en, &AlignmentOptions::new());
+    for pair in alignment.pairs {
+        for sentence in pair.primary {
+            println!("{}: {}", sentence.time_index / 1000, sentence.sentence);
         }
-        for sentences in test_en.sentences.get(&key) {
-            for sentence in sentences {
-                println!("{}: {}", key, sentence.sentence);
-            }
+        for sentence in pair.secondary {
+            println!("{}: {}", sentence.time_index / 1000, sentence.sentence);
         }
     }
 }
@@ -0,0 +1,208 @@
+pub mod fsrs;
+pub mod leitner;
+pub mod sm2;
+
+pub use self::fsrs::{Fsrs, FsrsState};
+pub use self::leitner::Leitner;
+pub use self::sm2::{Sm2, Sm2State};
+
+use crate::flashcard::{FlashCard, Grade, ProficiencyLevel, Review};
+use std::cell::Cell;
+use std::time::{Duration, SystemTime};
+
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+///The longest interval between two reviews, about 100 years
+pub const MAX_INTERVAL: Duration = Duration::from_secs(36525 * 24 * 60 * 60);
+
+///Decides when a card is reviewed next. The schedulers keep their state in the review history of the cards,
+///so a deck can switch between them.
+pub trait Scheduler {
+    ///The time of the scheduler's clock
+    fn now(&self) -> SystemTime;
+
+    ///The time the card is due. Cards which were never reviewed are due right away.
+    fn due_time(&self, card: &FlashCard) -> Option<SystemTime>;
+
+    ///The proficiency of the card after the reviews in its history
+    fn proficiency(&self, card: &FlashCard) -> ProficiencyLevel;
+
+    ///Adds the answer to the history of the card and updates its proficiency
+    fn review(&self, card: &mut FlashCard, grade: Grade) {
+        let time = self.now();
+        if card.reviews.is_empty() {
+            card.initial_proficiency = card.proficiency;
+        }
+        card.reviews.push(Review { time, grade });
+        card.last_review = Some(time);
+        card.proficiency = self.proficiency(card);
+    }
+
+    fn is_due(&self, card: &FlashCard) -> bool {
+        self.is_due_at(card, self.now())
+    }
+
+    fn is_due_at(&self, card: &FlashCard, time: SystemTime) -> bool {
+        match self.due_time(card) {
+            Some(due) => due <= time,
+            None => true,
+        }
+    }
+
+    ///The cards due today, see `end_of_day`
+    fn due_queue<'a>(&self, cards: &'a [FlashCard]) -> Vec<&'a FlashCard> {
+        self.due_queue_until(cards, end_of_day(self.now()))
+    }
+
+    ///The cards due at `time`, the lowest proficiency first. Within a level reviewed cards come before new ones
+    ///and the longest overdue first.
+    fn due_queue_until<'a>(&self, cards: &'a [FlashCard], time: SystemTime) -> Vec<&'a FlashCard> {
+        let mut queue: Vec<&FlashCard> = cards
+            .iter()
+            .filter(|card| self.is_due_at(card, time))
+            .collect();
+        queue.sort_by_key(|card| {
+            let due = self.due_time(card);
+            (card.proficiency.index(), due.is_none(), due)
+        });
+        queue
+    }
+}
+
+///The last moment of the day of `time`. Days are counted in UTC, as `SystemTime` has no time zone.
+pub fn end_of_day(time: SystemTime) -> SystemTime {
+    let since_epoch = time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+    let days = since_epoch.as_secs() / DAY.as_secs();
+    SystemTime::UNIX_EPOCH + Duration::from_secs((days + 1) * DAY.as_secs())
+        - Duration::from_nanos(1)
+}
+
+///The level of a card reviewed in this interval, which is the box of the default Leitner intervals
+///of 1, 2, 4, 8 and 16 days the interval reaches
+pub fn proficiency_for_interval(interval: Duration) -> ProficiencyLevel {
+    let boxes = ProficiencyLevel::ALL.len() as u32;
+    let reached = (0..boxes)
+        .take_while(|i| interval >= DAY * 2u32.pow(*i))
+        .count();
+    ProficiencyLevel::ALL[reached.saturating_sub(1)]
+}
+
+///Source of the current time of a scheduler
+pub trait Clock {
+    fn now(&self) -> SystemTime;
+}
+
+///The time of the system
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+///A clock which only moves when it is told to, for tests and simulations
+#[derive(Debug)]
+pub struct ManualClock {
+    now: Cell<SystemTime>,
+}
+
+impl ManualClock {
+    pub fn new(now: SystemTime) -> ManualClock {
+        ManualClock {
+            now: Cell::new(now),
+        }
+    }
+
+    pub fn set(&self, now: SystemTime) {
+        self.now.set(now);
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        self.now.set(self.now.get() + duration);
+    }
+
+    pub fn advance_days(&self, days: u32) {
+        self.advance(DAY * days);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> SystemTime {
+        self.now.get()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flashcard::Language;
+
+    #[test]
+    fn test_proficiency_for_interval() {
+        assert_eq!(
+            proficiency_for_interval(DAY / 2),
+            ProficiencyLevel::LevelOne
+        );
+        assert_eq!(
+            proficiency_for_interval(DAY * 3),
+            ProficiencyLevel::LevelTwo
+        );
+        assert_eq!(
+            proficiency_for_interval(DAY * 4),
+            ProficiencyLevel::LevelThree
+        );
+        assert_eq!(
+            proficiency_for_interval(DAY * 100),
+            ProficiencyLevel::LevelFive
+        );
+    }
+
+    #[test]
+    fn test_end_of_day() {
+        let midnight = SystemTime::UNIX_EPOCH + DAY * 1000;
+        let end = midnight + DAY - Duration::from_nanos(1);
+        assert_eq!(end_of_day(midnight), end);
+        assert_eq!(end_of_day(midnight + Duration::from_secs(8 * 60 * 60)), end);
+        assert_eq!(end_of_day(end), end);
+        assert_eq!(end_of_day(end + Duration::from_nanos(1)), end + DAY);
+    }
+
+    #[test]
+    fn test_due_today() {
+        let leitner = Leitner::with_clock(ManualClock::new(SystemTime::UNIX_EPOCH + DAY * 1000));
+        let mut evening = FlashCard::new(String::from("noche"), Language::Spanish);
+        let mut tomorrow = FlashCard::new(String::from("mañana"), Language::Spanish);
+        leitner.clock.advance(Duration::from_secs(20 * 60 * 60));
+        leitner.review(&mut evening, Grade::Again);
+        leitner.clock.advance(Duration::from_secs(12 * 60 * 60));
+        leitner.review(&mut tomorrow, Grade::Again);
+        //in the morning "noche" is due in the evening and "mañana" the next morning
+        let cards = [evening, tomorrow];
+        assert!(!leitner.is_due(&cards[0]));
+        let queue: Vec<&str> = leitner
+            .due_queue(&cards)
+            .iter()
+            .map(|card| card.word.as_str())
+            .collect();
+        assert_eq!(queue, vec!["noche"]);
+        assert!(leitner.due_queue_until(&cards, leitner.now()).is_empty());
+    }
+
+    #[test]
+    fn test_switch_scheduler() {
+        let leitner = Leitner::with_clock(ManualClock::new(SystemTime::UNIX_EPOCH));
+        let mut card = FlashCard::new(String::from("tomar"), Language::Spanish);
+        for _ in 0..3 {
+            leitner.review(&mut card, Grade::Good);
+            leitner.clock.advance_days(6);
+        }
+        let sm2 = Sm2::with_clock(ManualClock::new(leitner.now()));
+        assert_eq!(sm2.state(&card).unwrap().repetitions, 3);
+        sm2.review(&mut card, Grade::Good);
+        assert_eq!(card.reviews.len(), 4);
+        assert_eq!(card.proficiency, ProficiencyLevel::LevelFive);
+    }
+}
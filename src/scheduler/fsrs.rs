@@ -0,0 +1,192 @@
+use super::{proficiency_for_interval, Clock, Scheduler, SystemClock, DAY, MAX_INTERVAL};
+use crate::flashcard::{FlashCard, Grade, ProficiencyLevel};
+use std::time::{Duration, SystemTime};
+
+const DECAY: f64 = -0.5;
+///Chosen so that the retrievability is 0.9 after `stability` days
+const FACTOR: f64 = 19.0 / 81.0;
+
+///Free Spaced Repetition Scheduler, version 4.5. Each card has a stability, the days after which it is
+///recalled with a probability of 90%, and a difficulty from 1 to 10.
+pub struct Fsrs<C: Clock = SystemClock> {
+    ///the weights of the model, trained on the review histories of many learners
+    pub weights: [f64; 17],
+    ///probability of recalling a card when it is due
+    pub desired_retention: f64,
+    pub clock: C,
+}
+
+///State of a card after replaying its reviews
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FsrsState {
+    ///days until the probability of recalling the card drops to 90%
+    pub stability: f64,
+    ///from 1 for the easiest cards to 10 for the hardest
+    pub difficulty: f64,
+}
+
+impl Fsrs<SystemClock> {
+    ///The default weights of FSRS 4.5 and a retention of 90%
+    pub fn new() -> Fsrs<SystemClock> {
+        Fsrs::with_clock(SystemClock)
+    }
+}
+
+impl Default for Fsrs<SystemClock> {
+    fn default() -> Self {
+        Fsrs::new()
+    }
+}
+
+impl<C: Clock> Fsrs<C> {
+    pub fn with_clock(clock: C) -> Fsrs<C> {
+        Fsrs {
+            weights: [
+                0.4872, 1.4003, 3.7145, 13.8206, 5.1618, 1.2298, 0.8975, 0.031, 1.6474, 0.1367,
+                1.0461, 2.1072, 0.0793, 0.3246, 1.587, 0.2272, 2.8755,
+            ],
+            desired_retention: 0.9,
+            clock,
+        }
+    }
+
+    ///Replays the reviews of the card, None if it was never reviewed
+    pub fn state(&self, card: &FlashCard) -> Option<FsrsState> {
+        let w = &self.weights;
+        let (first, rest) = card.reviews.split_first()?;
+        let mut state = FsrsState {
+            stability: w[rating(first.grade) as usize - 1],
+            difficulty: self.initial_difficulty(first.grade),
+        };
+        let mut last_time = first.time;
+        for review in rest {
+            let elapsed = review
+                .time
+                .duration_since(last_time)
+                .unwrap_or_default()
+                .as_secs_f64()
+                / DAY.as_secs_f64();
+            let retrievability = (1.0 + FACTOR * elapsed / state.stability).powf(DECAY);
+            state = self.next_state(state, retrievability, review.grade);
+            last_time = review.time;
+        }
+        Some(state)
+    }
+
+    ///Time from the last review until the probability of recalling the card drops to the desired retention
+    pub fn interval(&self, state: FsrsState) -> Duration {
+        let days = state.stability / FACTOR * (self.desired_retention.powf(1.0 / DECAY) - 1.0);
+        Duration::from_secs_f64(
+            (DAY.as_secs_f64() * days.round().max(1.0)).min(MAX_INTERVAL.as_secs_f64()),
+        )
+    }
+
+    fn initial_difficulty(&self, grade: Grade) -> f64 {
+        let w = &self.weights;
+        (w[4] - (rating(grade) - 3.0) * w[5]).clamp(1.0, 10.0)
+    }
+
+    fn next_state(&self, state: FsrsState, retrievability: f64, grade: Grade) -> FsrsState {
+        let w = &self.weights;
+        let FsrsState {
+            stability: s,
+            difficulty: d,
+        } = state;
+        let stability = if grade.is_correct() {
+            let hard_penalty = if grade == Grade::Hard { w[15] } else { 1.0 };
+            let easy_bonus = if grade == Grade::Easy { w[16] } else { 1.0 };
+            s * (1.0
+                + w[8].exp()
+                    * (11.0 - d)
+                    * s.powf(-w[9])
+                    * ((w[10] * (1.0 - retrievability)).exp() - 1.0)
+                    * hard_penalty
+                    * easy_bonus)
+        } else {
+            w[11]
+                * d.powf(-w[12])
+                * ((s + 1.0).powf(w[13]) - 1.0)
+                * (w[14] * (1.0 - retrievability)).exp()
+        };
+        //the difficulty moves with the grade and reverts a little to the one of a card answered "Good"
+        let difficulty = d - w[6] * (rating(grade) - 3.0);
+        let difficulty = w[7] * self.initial_difficulty(Grade::Good) + (1.0 - w[7]) * difficulty;
+        FsrsState {
+            stability,
+            difficulty: difficulty.clamp(1.0, 10.0),
+        }
+    }
+}
+
+///The grade as the rating from 1 to 4 used by the formulas
+fn rating(grade: Grade) -> f64 {
+    match grade {
+        Grade::Again => 1.0,
+        Grade::Hard => 2.0,
+        Grade::Good => 3.0,
+        Grade::Easy => 4.0,
+    }
+}
+
+impl<C: Clock> Scheduler for Fsrs<C> {
+    fn now(&self) -> SystemTime {
+        self.clock.now()
+    }
+
+    fn due_time(&self, card: &FlashCard) -> Option<SystemTime> {
+        let state = self.state(card)?;
+        card.last_review
+            .map(|last_review| last_review + self.interval(state))
+    }
+
+    fn proficiency(&self, card: &FlashCard) -> ProficiencyLevel {
+        self.state(card)
+            .map_or(ProficiencyLevel::LevelOne, |state| {
+                proficiency_for_interval(self.interval(state))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flashcard::Language;
+    use crate::scheduler::ManualClock;
+
+    #[test]
+    fn test_review() {
+        let fsrs = Fsrs::with_clock(ManualClock::new(SystemTime::UNIX_EPOCH + DAY * 1000));
+        let mut card = FlashCard::new(String::from("tomar"), Language::Spanish);
+        assert!(fsrs.is_due(&card));
+        fsrs.review(&mut card, Grade::Good);
+        let state = fsrs.state(&card).unwrap();
+        assert_eq!(state.stability, 3.7145);
+        assert_eq!(state.difficulty, 5.1618);
+        assert_eq!(fsrs.due_time(&card), Some(fsrs.now() + DAY * 4));
+        assert_eq!(card.proficiency, ProficiencyLevel::LevelThree);
+        fsrs.clock.advance_days(4);
+        fsrs.review(&mut card, Grade::Good);
+        let recalled = fsrs.state(&card).unwrap();
+        assert!(recalled.stability > state.stability * 2.0);
+        assert!((recalled.difficulty - state.difficulty).abs() < 1e-9);
+        fsrs.clock.advance_days(10);
+        fsrs.review(&mut card, Grade::Again);
+        let forgotten = fsrs.state(&card).unwrap();
+        assert!(forgotten.stability < recalled.stability / 2.0);
+        assert!(forgotten.difficulty > recalled.difficulty);
+        //a forgotten card keeps part of its stability, unlike in Leitner and SM-2
+        assert_eq!(card.proficiency, ProficiencyLevel::LevelTwo);
+    }
+
+    #[test]
+    fn test_retention() {
+        let mut fsrs = Fsrs::with_clock(ManualClock::new(SystemTime::UNIX_EPOCH));
+        let state = FsrsState {
+            stability: 10.0,
+            difficulty: 5.0,
+        };
+        assert_eq!(fsrs.interval(state), DAY * 10);
+        fsrs.desired_retention = 0.8;
+        assert!(fsrs.interval(state) > DAY * 20);
+    }
+}
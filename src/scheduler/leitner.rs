@@ -0,0 +1,170 @@
+use super::{Clock, Scheduler, SystemClock, DAY};
+use crate::flashcard::{FlashCard, Grade, ProficiencyLevel};
+use std::time::{Duration, SystemTime};
+
+///Leitner system with a box for each `ProficiencyLevel`. A correct answer moves a card to the next box,
+///a wrong one back to `LevelOne`. A card is due when the interval of its box has passed since its last review.
+///The box is found by replaying the reviews of the card from its initial proficiency,
+///cards without reviews keep their stored proficiency.
+pub struct Leitner<C: Clock = SystemClock> {
+    ///time between two reviews of a card in each box, from `LevelOne` to `LevelFive`
+    pub intervals: [Duration; 5],
+    pub clock: C,
+}
+
+impl Leitner<SystemClock> {
+    ///Intervals of 1, 2, 4, 8 and 16 days
+    pub fn new() -> Leitner<SystemClock> {
+        Leitner::with_clock(SystemClock)
+    }
+}
+
+impl Default for Leitner<SystemClock> {
+    fn default() -> Self {
+        Leitner::new()
+    }
+}
+
+impl<C: Clock> Leitner<C> {
+    pub fn with_clock(clock: C) -> Leitner<C> {
+        Leitner {
+            intervals: [DAY, DAY * 2, DAY * 4, DAY * 8, DAY * 16],
+            clock,
+        }
+    }
+}
+
+impl<C: Clock> Scheduler for Leitner<C> {
+    fn now(&self) -> SystemTime {
+        self.clock.now()
+    }
+
+    fn due_time(&self, card: &FlashCard) -> Option<SystemTime> {
+        card.last_review
+            .map(|last_review| last_review + self.intervals[self.proficiency(card).index()])
+    }
+
+    ///Replays the reviews from the initial proficiency of the card, a wrong answer moves the card back to `LevelOne`
+    ///and a correct one to the next box
+    fn proficiency(&self, card: &FlashCard) -> ProficiencyLevel {
+        if card.reviews.is_empty() {
+            return card.proficiency;
+        }
+        card.reviews
+            .iter()
+            .fold(card.initial_proficiency, |level, review| {
+                if review.grade == Grade::Again {
+                    ProficiencyLevel::LevelOne
+                } else {
+                    level.next()
+                }
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flashcard::Language;
+    use crate::scheduler::{ManualClock, Sm2};
+
+    fn leitner() -> Leitner<ManualClock> {
+        Leitner::with_clock(ManualClock::new(SystemTime::UNIX_EPOCH + DAY * 1000))
+    }
+
+    fn card(word: &str) -> FlashCard {
+        FlashCard::new(String::from(word), Language::Spanish)
+    }
+
+    #[test]
+    fn test_review() {
+        let leitner = leitner();
+        let mut card = card("tomar");
+        assert!(leitner.is_due(&card));
+        leitner.review(&mut card, Grade::Good);
+        assert_eq!(card.proficiency, ProficiencyLevel::LevelTwo);
+        assert_eq!(card.last_review, Some(leitner.clock.now()));
+        assert!(!leitner.is_due(&card));
+        leitner.clock.advance_days(2);
+        assert!(leitner.is_due(&card));
+        for _ in 0..5 {
+            leitner.review(&mut card, Grade::Hard);
+        }
+        assert_eq!(card.proficiency, ProficiencyLevel::LevelFive);
+        leitner.clock.advance_days(15);
+        assert!(!leitner.is_due(&card));
+        leitner.review(&mut card, Grade::Again);
+        assert_eq!(card.proficiency, ProficiencyLevel::LevelOne);
+        assert_eq!(card.reviews.len(), 7);
+    }
+
+    #[test]
+    fn test_due_queue() {
+        let mut leitner = leitner();
+        leitner.intervals = [DAY, DAY * 3, DAY * 7, DAY * 14, DAY * 30];
+        let mut cards = vec![
+            card("nuevo"),
+            card("dinero"),
+            card("banco"),
+            card("coche"),
+            card("casa"),
+        ];
+        leitner.review(&mut cards[1], Grade::Good);
+        leitner.review(&mut cards[2], Grade::Again);
+        leitner.clock.advance_days(1);
+        leitner.review(&mut cards[3], Grade::Again);
+        leitner.review(&mut cards[4], Grade::Easy);
+        leitner.clock.advance_days(2);
+        let queue: Vec<&str> = leitner
+            .due_queue(&cards)
+            .iter()
+            .map(|card| card.word.as_str())
+            .collect();
+        //"casa" was moved to the second box two days ago, which has an interval of three days
+        assert_eq!(queue, vec!["banco", "coche", "nuevo", "dinero"]);
+    }
+
+    #[test]
+    fn test_proficiency() {
+        let leitner = leitner();
+        let mut card = card("tomar");
+        leitner.review(&mut card, Grade::Good);
+        assert_eq!(leitner.proficiency(&card), ProficiencyLevel::LevelTwo);
+        assert_eq!(leitner.proficiency(&card), ProficiencyLevel::LevelTwo);
+        assert_eq!(card.proficiency, ProficiencyLevel::LevelTwo);
+    }
+
+    #[test]
+    fn test_without_history() {
+        let leitner = leitner();
+        let mut card = card("tomar");
+        card.proficiency = ProficiencyLevel::LevelFour;
+        assert_eq!(leitner.proficiency(&card), ProficiencyLevel::LevelFour);
+        leitner.review(&mut card, Grade::Good);
+        assert_eq!(card.proficiency, ProficiencyLevel::LevelFive);
+        assert_eq!(card.initial_proficiency, ProficiencyLevel::LevelFour);
+        leitner.review(&mut card, Grade::Again);
+        leitner.review(&mut card, Grade::Good);
+        assert_eq!(leitner.proficiency(&card), ProficiencyLevel::LevelTwo);
+    }
+
+    #[test]
+    fn test_switch_from_sm2() {
+        let sm2 = Sm2::with_clock(ManualClock::new(SystemTime::UNIX_EPOCH));
+        let mut card = card("tomar");
+        for _ in 0..3 {
+            sm2.review(&mut card, Grade::Good);
+            sm2.clock.advance_days(20);
+        }
+        //SM-2 stored the level of a 15.6 day interval
+        assert_eq!(card.proficiency, ProficiencyLevel::LevelFour);
+        let leitner = leitner();
+        assert_eq!(leitner.proficiency(&card), ProficiencyLevel::LevelFour);
+        assert_eq!(
+            leitner.due_time(&card),
+            card.last_review.map(|time| time + DAY * 8)
+        );
+        leitner.review(&mut card, Grade::Good);
+        assert_eq!(card.proficiency, ProficiencyLevel::LevelFive);
+    }
+}
@@ -0,0 +1,160 @@
+use super::{proficiency_for_interval, Clock, Scheduler, SystemClock, DAY, MAX_INTERVAL};
+use crate::flashcard::{FlashCard, Grade, ProficiencyLevel};
+use std::time::{Duration, SystemTime};
+
+///SuperMemo 2. Each card has an ease factor which grows with easy answers and shrinks with hard ones,
+///the interval is multiplied by it after each correct answer, up to `MAX_INTERVAL`. As in the original algorithm
+///a wrong answer restarts the repetitions without changing the ease factor.
+pub struct Sm2<C: Clock = SystemClock> {
+    ///ease factor of a new card
+    pub initial_ease: f64,
+    ///the ease factor never drops below this
+    pub minimum_ease: f64,
+    pub clock: C,
+}
+
+///State of a card after replaying its reviews
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sm2State {
+    ///correct answers since the last wrong one
+    pub repetitions: u32,
+    pub ease: f64,
+    pub interval: Duration,
+}
+
+impl Sm2<SystemClock> {
+    ///Ease factor 2.5 for new cards and at least 1.3, as in the original algorithm
+    pub fn new() -> Sm2<SystemClock> {
+        Sm2::with_clock(SystemClock)
+    }
+}
+
+impl Default for Sm2<SystemClock> {
+    fn default() -> Self {
+        Sm2::new()
+    }
+}
+
+impl<C: Clock> Sm2<C> {
+    pub fn with_clock(clock: C) -> Sm2<C> {
+        Sm2 {
+            initial_ease: 2.5,
+            minimum_ease: 1.3,
+            clock,
+        }
+    }
+
+    ///Replays the reviews of the card, None if it was never reviewed
+    pub fn state(&self, card: &FlashCard) -> Option<Sm2State> {
+        if card.reviews.is_empty() {
+            return None;
+        }
+        let mut state = Sm2State {
+            repetitions: 0,
+            ease: self.initial_ease,
+            interval: Duration::from_secs(0),
+        };
+        for review in card.reviews.iter() {
+            //the quality of the answer from 0 to 5 in the original algorithm
+            let quality: f64 = match review.grade {
+                Grade::Again => 1.0,
+                Grade::Hard => 3.0,
+                Grade::Good => 4.0,
+                Grade::Easy => 5.0,
+            };
+            if review.grade.is_correct() {
+                state.interval = match state.repetitions {
+                    0 => DAY,
+                    1 => DAY * 6,
+                    _ => Duration::from_secs_f64(
+                        (state.interval.as_secs_f64() * state.ease).min(MAX_INTERVAL.as_secs_f64()),
+                    ),
+                };
+                state.repetitions += 1;
+                let miss = 5.0 - quality;
+                state.ease =
+                    (state.ease + 0.1 - miss * (0.08 + miss * 0.02)).max(self.minimum_ease);
+            } else {
+                state.repetitions = 0;
+                state.interval = DAY;
+            }
+        }
+        Some(state)
+    }
+}
+
+impl<C: Clock> Scheduler for Sm2<C> {
+    fn now(&self) -> SystemTime {
+        self.clock.now()
+    }
+
+    fn due_time(&self, card: &FlashCard) -> Option<SystemTime> {
+        let state = self.state(card)?;
+        card.last_review
+            .map(|last_review| last_review + state.interval)
+    }
+
+    fn proficiency(&self, card: &FlashCard) -> ProficiencyLevel {
+        self.state(card)
+            .map_or(ProficiencyLevel::LevelOne, |state| {
+                proficiency_for_interval(state.interval)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flashcard::Language;
+    use crate::scheduler::ManualClock;
+
+    #[test]
+    fn test_review() {
+        let sm2 = Sm2::with_clock(ManualClock::new(SystemTime::UNIX_EPOCH + DAY * 1000));
+        let mut card = FlashCard::new(String::from("tomar"), Language::Spanish);
+        assert!(sm2.state(&card).is_none());
+        assert!(sm2.is_due(&card));
+        sm2.review(&mut card, Grade::Good);
+        assert_eq!(sm2.due_time(&card), Some(sm2.now() + DAY));
+        sm2.clock.advance_days(1);
+        sm2.review(&mut card, Grade::Easy);
+        let state = sm2.state(&card).unwrap();
+        assert_eq!(state.repetitions, 2);
+        assert_eq!(state.interval, DAY * 6);
+        assert!((state.ease - 2.6).abs() < 1e-9);
+        assert_eq!(card.proficiency, ProficiencyLevel::LevelThree);
+        sm2.clock.advance_days(6);
+        sm2.review(&mut card, Grade::Good);
+        assert_eq!(sm2.state(&card).unwrap().interval, DAY.mul_f64(6.0 * 2.6));
+        assert_eq!(card.proficiency, ProficiencyLevel::LevelFour);
+        sm2.review(&mut card, Grade::Again);
+        let state = sm2.state(&card).unwrap();
+        assert_eq!(state.repetitions, 0);
+        assert_eq!(state.interval, DAY);
+        assert!((state.ease - 2.6).abs() < 1e-9);
+        assert_eq!(card.proficiency, ProficiencyLevel::LevelOne);
+    }
+
+    #[test]
+    fn test_minimum_ease() {
+        let sm2 = Sm2::with_clock(ManualClock::new(SystemTime::UNIX_EPOCH));
+        let mut card = FlashCard::new(String::from("banco"), Language::Spanish);
+        for _ in 0..10 {
+            sm2.review(&mut card, Grade::Hard);
+        }
+        assert_eq!(sm2.state(&card).unwrap().ease, 1.3);
+        sm2.review(&mut card, Grade::Again);
+        assert_eq!(sm2.state(&card).unwrap().ease, 1.3);
+    }
+
+    #[test]
+    fn test_maximum_interval() {
+        let sm2 = Sm2::with_clock(ManualClock::new(SystemTime::UNIX_EPOCH));
+        let mut card = FlashCard::new(String::from("casa"), Language::Spanish);
+        for _ in 0..50 {
+            sm2.review(&mut card, Grade::Good);
+        }
+        assert_eq!(sm2.state(&card).unwrap().interval, MAX_INTERVAL);
+        assert_eq!(sm2.due_time(&card), Some(sm2.now() + MAX_INTERVAL));
+    }
+}
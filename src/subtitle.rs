@@ -1,35 +1,42 @@
+pub mod alignment;
+pub mod annotations;
+pub mod ass;
+pub mod bilingual;
+pub mod colour;
+pub mod encoding;
+pub mod error;
+pub mod format;
+pub mod markup;
+pub mod segmenter;
+pub mod sentences;
+pub mod srt;
+pub mod sync;
+pub mod timing;
+pub mod turns;
+pub mod vtt;
+
+pub use self::alignment::{Alignment, AlignmentOptions};
+pub use self::annotations::{Annotation, AnnotationKind};
+pub use self::bilingual::{BilingualOptions, BilingualSubtitle};
+pub use self::colour::Colour;
+pub use self::encoding::Encoding;
+pub use self::error::SubtitleError;
+pub use self::format::SubtitleFormat;
+pub use self::markup::{Position, Span, SpanStyle};
+pub use self::segmenter::{RuleSegmenter, SentenceSegmenter};
+pub use self::sentences::SentenceList;
+pub use self::sync::{SyncEstimate, SyncOptions};
+pub use self::timing::TimeMapping;
+pub use self::turns::Utterance;
 use crate::toolbox;
-use regex::{Match, Regex};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
 use std::fmt;
 use std::fs;
+use std::io;
+use std::path::Path;
 use std::rc::Rc;
 use std::time::Duration;
 
-lazy_static! {
-    // let regex = r#"(?m)^\d{1,4}\s+\n"#;
-    static ref SECTION_REGEX: Regex = Regex::new(r#"(?m)^\d{1,4}\s+\n"#).unwrap();
-    // static ref PARTIAL_START_REGEX: Regex = Regex::new(r#"^(?P<before_sentence>[a-z][^\.!?]*[\.!?]?)"#).unwrap();
-    // static ref SENTENCE_REGEX: Regex = Regex::new(r#"(?P<sentence>¿?¡?[A-Z0-9][^\.!?]*[\.!?][\.]{0,2})"#).unwrap();
-    // static ref PARTIAL_END_REGEX: Regex = Regex::new(r#"¿?¡?[0-9A-Z][^\.!?]*[\.!?]\s?(?P<after_sentence>[A-Z][^\.!?]*$)"#).unwrap();
-    //Partial Regex is to identify if the whole String is a partial sentence. It cannot contain two partial sentences
-    //A Partial sentence is also if the whole input is a beginning of a sentence.
-    static ref PARTIAL_REGEX: Regex = Regex::new(r#"^(¿?¡?[A-Za-z0-9][^\.!?]*)$"#).unwrap();
-    //If we dont have a match for a partial we check if we have a ending of a sentence in the beginning of the string.
-    static ref PARTIAL_ENDING_REGEX: Regex = Regex::new(r#"^(?P<sentence_ending>¿?¡?[a-z][^\.!?]*[\.!?]{1}).*$"#).unwrap();
-    //If we have a ending sentence at the beginning then we either have either
-    // the start of a new partial sentence
-    // one or more  complete sentences
-    // one or more complete sentences and the start of a new partial sentence
-    //so we are going to first look for the start of a new partial sentences
-    static ref PARTIAL_BEGINNING_REGEX: Regex = Regex::new(r#"[^\.!?]*[\.!?]\s?(?P<sentence_beginning>[A-Z][^\.!?]*$)"#).unwrap();
-    //and we are collecting all complete sentences
-    static ref SENTENCE_REGEX: Regex = Regex::new(r#"(?P<sentence>¿?¡?[A-Z0-9][^\.!?]*[\.!?][\.]{0,2})"#).unwrap();
-
-
-
-}
 #[derive(Eq, PartialEq, Debug)]
 pub struct SentenceExtractionResult {
     pub end_of_a_sentence: Option<String>,
@@ -38,31 +45,94 @@ pub struct SentenceExtractionResult {
     pub partial: Option<String>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct SubtitleSection {
     pub id: u64,
     pub from: Duration,
     pub to: Duration,
     pub time_index: u128,
     pub text: String,
+    ///text lines as they appear in the file including markup. The line breaks often separate two speakers.
+    #[serde(default)]
+    pub raw_lines: Vec<String>,
+    ///speaker of the section if the format provides one, e.g. WebVTT `<v Speaker>` tags
+    #[serde(default)]
+    pub speaker: Option<String>,
+    ///style of the section if the format provides one, e.g. the ASS/SSA `Style` column
+    #[serde(default)]
+    pub style: Option<String>,
+    ///the text split at the speaker turns
+    #[serde(default)]
+    pub utterances: Vec<Utterance>,
+    ///sound effects, speaker labels, lyrics and on-screen text, which are not part of the text
+    #[serde(default)]
+    pub annotations: Vec<Annotation>,
+    ///the cue text with its formatting. Unlike `text` it keeps the line breaks, dashes and annotations.
+    ///The exporters write the spans as long as `text` is unchanged, see `SubtitleSection::export_spans`.
+    #[serde(default)]
+    pub spans: Vec<Span>,
+    ///`text` at the time the spans were set
+    #[serde(default)]
+    pub spans_text: String,
+    #[serde(default)]
+    pub position: Option<Position>,
+    ///true if the section is spoken by a narrator or an off-screen voice, see `markup::mark_narration`
+    #[serde(default)]
+    pub narration: bool,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct SubtitleSentence {
     ///time_index is the time in milliseconds in which the sentence startet
     pub time_index: u128,
     ///the sentence
     pub sentence: String,
+    ///ids of the sections the sentence is made of
+    #[serde(default)]
+    pub section_ids: Vec<u64>,
+    ///start of the first section of the sentence
+    #[serde(default)]
+    pub start: Duration,
+    ///end of the last section of the sentence
+    #[serde(default)]
+    pub end: Duration,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct Subtitle {
     pub name: String,
-    pub sentences: HashMap<u128, Vec<SubtitleSentence>>,
+    ///sentences sorted by their start
+    pub sentences: SentenceList,
     pub sections: Vec<SubtitleSection>,
+    ///path of the file the subtitle was read from
+    #[serde(default)]
+    pub source: Option<String>,
+    #[serde(default)]
+    pub format: SubtitleFormat,
+    ///encoding of the file before it was transcoded to UTF-8
+    #[serde(default)]
+    pub encoding: Encoding,
+    ///blocks which were skipped while parsing in `ParseMode::Lenient`
+    #[serde(skip)]
+    pub warnings: Vec<SubtitleError>,
+}
+
+///Describes how to deal with malformed blocks while parsing a subtitle file
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum ParseMode {
+    ///the first malformed block aborts parsing with an error
+    Strict,
+    ///malformed blocks are skipped and collected in `Subtitle::warnings`
+    Lenient,
 }
 
 impl SentenceExtractionResult {
+    #[allow(
+        clippy::needless_late_init,
+        clippy::search_is_some,
+        clippy::manual_map,
+        clippy::redundant_field_names
+    )]
     pub fn from_string(input: &str) -> SentenceExtractionResult {
         let sentence = Rc::new(String::from(input));
         let ending_check = Rc::clone(&sentence);
@@ -136,6 +206,34 @@ impl SentenceExtractionResult {
     }
 }
 
+impl SubtitleSentence {
+    ///Starts a sentence in the section
+    pub fn from_section(section: &SubtitleSection, sentence: String) -> SubtitleSentence {
+        SubtitleSentence {
+            time_index: section.time_index,
+            sentence,
+            section_ids: vec![section.id],
+            start: section.from,
+            end: section.to,
+        }
+    }
+
+    ///Continues the sentence with text of the following section
+    pub fn append(&mut self, section: &SubtitleSection, text: &str) {
+        self.sentence = format!("{} {}", self.sentence, text);
+        if self.section_ids.last() != Some(&section.id) {
+            self.section_ids.push(section.id);
+        }
+        self.end = self.end.max(section.to);
+    }
+}
+
+impl Default for SubtitleSection {
+    fn default() -> Self {
+        SubtitleSection::new()
+    }
+}
+
 impl SubtitleSection {
     pub fn new() -> SubtitleSection {
         SubtitleSection {
@@ -144,172 +242,85 @@ impl SubtitleSection {
             to: Duration::new(0, 0),
             time_index: 0,
             text: String::new(),
+            raw_lines: Vec::new(),
+            speaker: None,
+            style: None,
+            utterances: Vec::new(),
+            annotations: Vec::new(),
+            spans: Vec::new(),
+            spans_text: String::new(),
+            position: None,
+            narration: false,
         }
     }
 
-    pub fn from_string(block: String) -> Option<SubtitleSection> {
-        let mut lines: Vec<&str> = block.split("\r").collect();
-        //first item is id
-        let id = lines.get(0).unwrap().parse::<u64>().unwrap();
-        //second item is the time
-        let (from, to) = toolbox::get_times(lines.get(1).unwrap());
-        //3 item to last item is text
-        let text = match toolbox::get_text(lines.split_off(2)) {
-            Some(t) => String::from(toolbox::special_language_replacements(
-                toolbox::clean_content_string(&t).trim(),
-            )),
-            None => {
-                println!("Error for id: {}", id);
-                return None;
-            }
-        };
-        if text.is_empty() {
+    ///Parses a single SRT block consisting of the id, the time line and the text lines.
+    ///Returns `Ok(None)` if the text is empty after cleaning.
+    pub fn from_string(block: String) -> Result<Option<SubtitleSection>, SubtitleError> {
+        srt::parse_block(&toolbox::split_lines(block.trim()), 1, 1)
+    }
+
+    ///Returns the text to write to a subtitle file. Sections without text return their annotations, one per line.
+    pub fn export_text(&self) -> String {
+        if !self.text.is_empty() {
+            return self.text.clone();
+        }
+        self.annotations
+            .iter()
+            .filter(|a| a.kind != AnnotationKind::SpeakerLabel)
+            .map(|a| a.to_string())
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    ///Sets the formatted text of the current `text`
+    pub fn set_spans(&mut self, spans: Vec<Span>) {
+        self.spans = spans;
+        self.spans_text = self.text.clone();
+    }
+
+    ///Returns the spans to write to a subtitle file. None if there are no spans or `text` was changed
+    ///after they were set, so the exporters write the changed text instead.
+    pub fn export_spans(&self) -> Option<&[Span]> {
+        if self.spans.is_empty() || self.text != self.spans_text {
             None
         } else {
-            Some(SubtitleSection {
-                id: id,
-                from: from,
-                to: to,
-                time_index: from.as_millis(),
-                text: text,
-            })
+            Some(&self.spans)
+        }
+    }
+
+    ///Returns the text of each utterance with its turn.
+    ///Sections stored without utterances return their whole text as turn 0.
+    pub fn turns(&self) -> Vec<(&SubtitleSection, usize, &str)> {
+        if self.utterances.is_empty() && !self.text.is_empty() {
+            return vec![(self, 0, self.text.as_str())];
         }
+        self.utterances
+            .iter()
+            .map(|u| (self, u.turn, u.text.as_str()))
+            .collect()
     }
 
     pub fn add_text(&mut self, text: &str) {
         self.text.push_str(text);
     }
 
-    pub fn to_string(&self) -> String {
+    ///Returns the text followed by a line break
+    pub fn text_line(&self) -> String {
         format!("{}\n", self.text)
     }
 
-    pub fn extract_sentences(contents: &str) -> SentenceExtractionResult {
-        //First we check if there is a sentence at all
-        // let mut all_sentences: Option<Vec<String>> =None;
-        let sentences: Option<Vec<String>>;
-        let mut all_sentences = Vec::new();
-        let mut ending_of_a_sentence = None;
-        let mut beginning_of_a_sentence = None;
+    ///Returns the text followed by a line break. Unlike the `Display` output it has no ids and times.
+    #[deprecated(note = "use `text_line`, `to_string` is shadowing `Display`")]
+    #[allow(clippy::inherent_to_string_shadow_display)]
+    pub fn to_string(&self) -> String {
+        self.text_line()
+    }
 
-        //---------------------
-        //Partial Regex is to identify if the whole String is a partial sentence. It cannot contain two partial sentences
-        if PARTIAL_REGEX.is_match(&contents) {
-            return SentenceExtractionResult {
-                end_of_a_sentence: None,
-                sentences: None,
-                begin_of_a_sentence: None,
-                partial: Some(String::from(contents)),
-            };
-        }
-        //If we dont have a match for a partial we check if we have a ending of a sentence in the beginning of the string.
-        if PARTIAL_ENDING_REGEX.is_match(&contents) {
-            let caps = PARTIAL_ENDING_REGEX.captures(&contents).unwrap(); //we can do that as we know there is a match and only one
-            ending_of_a_sentence =
-                Some(String::from(caps.name("sentence_ending").unwrap().as_str()));
-        }
-        //If we have a ending sentence at the beginning then we either have either
-        // the start of a new partial sentence
-        // one or more  complete sentences
-        // one or more complete sentences and the start of a new partial sentence
-        //so we are going to first look for the start of a new partial sentences
-        if PARTIAL_BEGINNING_REGEX.is_match(&contents) {
-            let caps = PARTIAL_BEGINNING_REGEX.captures(&contents).unwrap();
-            beginning_of_a_sentence = Some(String::from(
-                caps.name("sentence_beginning").unwrap().as_str(),
-            ));
-        }
-        //and then we are collecting all complete sentences
-        if SENTENCE_REGEX.is_match(&contents) {
-            //if there is at least one sentence we should look for all complete sentences in the contents
-            //and add each sentence to our result struct
-            for caps in SENTENCE_REGEX.captures_iter(&contents) {
-                match caps.name("sentence") {
-                    Some(c) => {
-                        all_sentences.push(String::from(c.as_str()));
-                    }
-                    None => println!("Sentence: None"),
-                };
-            }
-            sentences = Some(all_sentences);
-        } else {
-            sentences = None;
-        }
-        SentenceExtractionResult {
-            sentences: sentences,
-            begin_of_a_sentence: beginning_of_a_sentence,
-            end_of_a_sentence: ending_of_a_sentence,
-            partial: None,
-        }
+    ///Extracts the sentences with the rules of `RuleSegmenter::new()`
+    pub fn extract_sentences(contents: &str) -> SentenceExtractionResult {
+        RuleSegmenter::new().segment(contents, false)
     }
-    //------------------------------
-    // if SENTENCE_REGEX.is_match(&contents) {
-    //     //if there is at least one sentence we should look for all complete sentences in the contents
-    //     //and add each sentence to our result struct
-    //     for caps in SENTENCE_REGEX.captures_iter(&contents) {
-    //         match caps.name("sentence") {
-    //             Some(c) => {
-    //                 all_sentences.push(String::from(c.as_str()));
-    //                 sentence_found = true;
-    //             }
-    //             None => println!("Sentence: None"),
-    //         };
-    //     }
-    //     sentences = Some(all_sentences);
-    // } else {
-    //     sentences = None;
-    // }
-    // //then we should check if there is an partial sentence at the Beginning
-    // if PARTIAL_START_REGEX.is_match(&contents) {
-    //     //There should be only one
-    //     //Todo: assert it
-    //     match PARTIAL_START_REGEX.captures(&contents) {
-    //         Some(caps) => {
-    //             match caps.name("before_sentence") {
-    //                 Some(c) => before_sentence = String::from(c.as_str()),
-    //                 None => panic!(
-    //                     "Shouldn't happen as we named the regex ourselve check fo a match."
-    //                 ),
-    //             };
-    //         }
-    //         None => panic!("Shouldn't happen as we initiall check fo a match."),
-    //     };
-    //     before = Some(before_sentence);
-    // } else {
-    //     before = None;
-    // };
-    // //and if there is a partial sentence at the end.
-    // if PARTIAL_END_REGEX.is_match(&contents) {
-    //     //There should be only one
-    //     //Todo: assert it
-    //     match PARTIAL_END_REGEX.captures(&contents) {
-    //         Some(caps) => {
-    //             match caps.name("after_sentence") {
-    //                 Some(c) => after_sentence = String::from(c.as_str()),
-    //                 None => panic!(
-    //                     "Shouldn't happen as we named the regex ourselve check fo a match."
-    //                 ),
-    //             };
-    //         }
-    //         None => panic!("Shouldn't happen as we initiall check fo a match."),
-    //     };
-    //     after = Some(after_sentence);
-    // } else {
-    //     after = None;
-    // };
-    // // lets check if we found either a sentence or a before or an after. If not, then the whole input is a partial sentence
-    // if before.is_none() && sentences.is_none() && after.is_none() {
-    //     //assert that before is None. after we will overwrite anyway. Also sentence should be none
-    //     //we store this whole partial sentence in "before"
-    //     partial = Some(String::from(contents));
-    // }
-    // SentenceExtractionResult {
-    //     sentences,
-    //     before,
-    //     after,
-    //     partial,
-    // }
-    // }
 }
 impl fmt::Display for SubtitleSection {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -330,48 +341,272 @@ impl Subtitle {
     pub fn new(name: String) -> Subtitle {
         Subtitle {
             name,
-            sentences: HashMap::new(),
+            sentences: SentenceList::new(),
             sections: Vec::new(),
+            source: None,
+            format: SubtitleFormat::Srt,
+            encoding: Encoding::Utf8,
+            warnings: Vec::new(),
+        }
+    }
+
+    ///Reads a subtitle file in `ParseMode::Strict`. See `Subtitle::load_with_mode`.
+    pub fn load(path: &str) -> Result<Subtitle, SubtitleError> {
+        Subtitle::load_with_mode(path, ParseMode::Strict)
+    }
+
+    ///Reads a subtitle file and parses it according to its format.
+    ///The format is detected from the content and only if that fails from the file extension.
+    ///The name of the subtitle is the file name without extension.
+    pub fn load_with_mode(path: &str, mode: ParseMode) -> Result<Subtitle, SubtitleError> {
+        let name = Path::new(path)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| String::from(path));
+        Subtitle::parse_file(path, |contents| {
+            let format = SubtitleFormat::detect(contents)
+                .or_else(|| SubtitleFormat::from_extension(path))
+                .ok_or_else(|| SubtitleError::UnknownFormat {
+                    path: String::from(path),
+                })?;
+            Subtitle::from_string_with_format(&name, contents, format, mode)
+        })
+    }
+
+    ///Reads a subtitle file like `Subtitle::load_with_mode` and splits its sentences with the segmenter,
+    ///e.g. `RuleSegmenter::for_language(&Language::German)`
+    pub fn load_with_segmenter(
+        path: &str,
+        mode: ParseMode,
+        segmenter: &dyn SentenceSegmenter,
+    ) -> Result<Subtitle, SubtitleError> {
+        let mut subtitle = Subtitle::load_with_mode(path, mode)?;
+        subtitle.resegment(segmenter);
+        Ok(subtitle)
+    }
+
+    ///Extracts the sentences again from the sections with the segmenter
+    pub fn resegment(&mut self, segmenter: &dyn SentenceSegmenter) {
+        self.resegment_with(segmenter, false);
+    }
+
+    ///Extracts the sentences again from the sections with the segmenter.
+    ///With `lyrics` the lines of the songs are added as sentences, see `Subtitle::lyrics`.
+    pub fn resegment_with(&mut self, segmenter: &dyn SentenceSegmenter, lyrics: bool) {
+        let mut sentences = Subtitle::extract_sentences_from_sections(&self.sections, segmenter);
+        if lyrics {
+            for sentence in self.lyrics() {
+                sentences.push(sentence);
+            }
+        }
+        self.sentences = sentences;
+    }
+
+    ///The lines of the songs as sentences, one per line. They aren't part of `sentences` by default.
+    pub fn lyrics(&self) -> Vec<SubtitleSentence> {
+        self.annotations(AnnotationKind::Lyrics)
+            .into_iter()
+            .map(|(section, lyrics)| SubtitleSentence::from_section(section, lyrics.text.clone()))
+            .collect()
+    }
+
+    ///Reads the file transcoded to UTF-8, parses it and records where it came from
+    fn parse_file<F>(filename: &str, parse: F) -> Result<Subtitle, SubtitleError>
+    where
+        F: FnOnce(&str) -> Result<Subtitle, SubtitleError>,
+    {
+        let (contents, encoding) = encoding::read_to_string(filename)?;
+        let mut subtitle = parse(&contents)?;
+        subtitle.source = Some(String::from(filename));
+        subtitle.encoding = encoding;
+        Ok(subtitle)
+    }
+
+    ///Parses the content of a subtitle file in the given format
+    pub fn from_string_with_format(
+        name: &str,
+        contents: &str,
+        format: SubtitleFormat,
+        mode: ParseMode,
+    ) -> Result<Subtitle, SubtitleError> {
+        match format {
+            SubtitleFormat::Srt => Subtitle::from_srt_string(name, contents, mode),
+            SubtitleFormat::WebVtt => Subtitle::from_vtt_string(name, contents, mode),
+            SubtitleFormat::Ass => Subtitle::from_ass_string(name, contents, mode),
         }
     }
-    // pub sentences: HashMap<u128, Vec<SubtitleSection>>,
     // pub sections: Vec<Vec<SubtitleSection>>,
 
-    pub fn from_file(name: &str, filename: &str) -> Option<Subtitle> {
-        let contents = fs::read_to_string(filename).expect("Something went wrong reading the file");
-        let matches: Vec<Match> = SECTION_REGEX.find_iter(&contents).collect();
-        let mut sections: Vec<SubtitleSection> = Vec::new();
-        for i in 0..matches.len() - 1 {
-            match SubtitleSection::from_string(String::from(
-                contents
-                    .get(matches[i].start()..matches[i + 1].start() - 1)
-                    .unwrap()
-                    .trim(),
-            )) {
-                Some(s) => {
-                    sections.push(s);
-                }
-                None => (),
-            };
+    ///Reads a SRT file in `ParseMode::Strict`
+    pub fn from_file(name: &str, filename: &str) -> Result<Subtitle, SubtitleError> {
+        Subtitle::from_file_with_mode(name, filename, ParseMode::Strict)
+    }
+
+    pub fn from_file_with_mode(
+        name: &str,
+        filename: &str,
+        mode: ParseMode,
+    ) -> Result<Subtitle, SubtitleError> {
+        Subtitle::parse_file(filename, |contents| {
+            Subtitle::from_srt_string(name, contents, mode)
+        })
+    }
+
+    ///Parses the content of a SRT file
+    pub fn from_srt_string(
+        name: &str,
+        contents: &str,
+        mode: ParseMode,
+    ) -> Result<Subtitle, SubtitleError> {
+        let (sections, warnings) = srt::parse(contents, mode)?;
+        Ok(Subtitle::from_sections(
+            name,
+            SubtitleFormat::Srt,
+            sections,
+            warnings,
+        ))
+    }
+
+    pub fn from_vtt_file(
+        name: &str,
+        filename: &str,
+        mode: ParseMode,
+    ) -> Result<Subtitle, SubtitleError> {
+        Subtitle::parse_file(filename, |contents| {
+            Subtitle::from_vtt_string(name, contents, mode)
+        })
+    }
+
+    ///Parses the content of a WebVTT file
+    pub fn from_vtt_string(
+        name: &str,
+        contents: &str,
+        mode: ParseMode,
+    ) -> Result<Subtitle, SubtitleError> {
+        let (sections, warnings) = vtt::parse(contents, mode)?;
+        Ok(Subtitle::from_sections(
+            name,
+            SubtitleFormat::WebVtt,
+            sections,
+            warnings,
+        ))
+    }
+
+    pub fn from_ass_file(
+        name: &str,
+        filename: &str,
+        mode: ParseMode,
+    ) -> Result<Subtitle, SubtitleError> {
+        Subtitle::parse_file(filename, |contents| {
+            Subtitle::from_ass_string(name, contents, mode)
+        })
+    }
+
+    ///Parses the content of an Advanced SubStation Alpha (.ass) or SubStation Alpha (.ssa) file
+    pub fn from_ass_string(
+        name: &str,
+        contents: &str,
+        mode: ParseMode,
+    ) -> Result<Subtitle, SubtitleError> {
+        let (sections, warnings) = ass::parse(contents, mode)?;
+        Ok(Subtitle::from_sections(
+            name,
+            SubtitleFormat::Ass,
+            sections,
+            warnings,
+        ))
+    }
+
+    fn from_sections(
+        name: &str,
+        format: SubtitleFormat,
+        mut sections: Vec<SubtitleSection>,
+        warnings: Vec<SubtitleError>,
+    ) -> Subtitle {
+        turns::number(&mut sections);
+        markup::mark_narration(&mut sections);
+        let sentences = Subtitle::extract_sentences_from_sections(&sections, &RuleSegmenter::new());
+        Subtitle {
+            name: String::from(name),
+            sections,
+            sentences,
+            source: None,
+            format,
+            encoding: Encoding::Utf8,
+            warnings,
         }
-        //we have one section left at the end
-        match SubtitleSection::from_string(String::from(
-            contents
-                .get(matches[matches.len() - 1].start()..)
-                .unwrap()
-                .trim(),
-        )) {
-            Some(s) => {
-                sections.push(s);
+    }
+
+    ///Returns the annotations of the given kind, e.g. the lyrics of the songs, with their section
+    pub fn annotations(&self, kind: AnnotationKind) -> Vec<(&SubtitleSection, &Annotation)> {
+        self.sections
+            .iter()
+            .flat_map(|section| section.annotations.iter().map(move |a| (section, a)))
+            .filter(|(_, a)| a.kind == kind)
+            .collect()
+    }
+
+    ///Moves all sections and sentences by `milliseconds`, which can be negative
+    pub fn shift(&mut self, milliseconds: i64) {
+        self.retime(&TimeMapping::shift(milliseconds));
+    }
+
+    ///Adapts the times to a video with a different framerate. See `TimeMapping::rescale`.
+    ///Returns false and leaves the subtitle unchanged if a framerate is not a positive number.
+    pub fn rescale(&mut self, from_fps: f64, to_fps: f64) -> bool {
+        match TimeMapping::rescale(from_fps, to_fps) {
+            Some(mapping) => {
+                self.retime(&mapping);
+                true
             }
-            None => (),
-        };
-        let sentences = Subtitle::extract_sentences_from_sections(&sections);
-        Some(Subtitle {
-            name: String::from(name),
-            sections: sections,
-            sentences: sentences,
-        })
+            None => false,
+        }
+    }
+
+    ///Linear resync which moves the time `a` to `x` and `b` to `y`, e.g. the start of the first and the last section.
+    ///Returns false and leaves the subtitle unchanged if the two points don't define a valid mapping.
+    pub fn resync(&mut self, a: Duration, x: Duration, b: Duration, y: Duration) -> bool {
+        match TimeMapping::from_points(a, x, b, y) {
+            Some(mapping) => {
+                self.retime(&mapping);
+                true
+            }
+            None => false,
+        }
+    }
+
+    ///Applies the mapping to the times of the sections and the sentences
+    pub fn retime(&mut self, mapping: &TimeMapping) {
+        for section in self.sections.iter_mut() {
+            section.from = mapping.map(section.from);
+            section.to = mapping.map(section.to);
+            section.time_index = section.from.as_millis();
+        }
+        let mut sentences = std::mem::take(&mut self.sentences).into_vec();
+        for sentence in sentences.iter_mut() {
+            sentence.time_index = mapping.map_millis(sentence.time_index);
+            sentence.start = mapping.map(sentence.start);
+            sentence.end = mapping.map(sentence.end);
+        }
+        self.sentences = SentenceList::from_vec(sentences);
+    }
+
+    ///Returns the sections as a SRT file, numbered from 1
+    pub fn to_srt_string(&self) -> String {
+        srt::write(&self.sections)
+    }
+
+    ///Returns the sections as a WebVTT file, numbered from 1
+    pub fn to_vtt_string(&self) -> String {
+        vtt::write(&self.sections)
+    }
+
+    pub fn write_srt(&self, filename: &str) -> io::Result<()> {
+        fs::write(filename, self.to_srt_string())
+    }
+
+    pub fn write_vtt(&self, filename: &str) -> io::Result<()> {
+        fs::write(filename, self.to_vtt_string())
     }
 
     /// we have a problem with a section containing the end of a sentence and the beginning of a new one.
@@ -379,163 +614,84 @@ impl Subtitle {
     /// "is in the river. But to save him,""
     /// and following sections where the sentence end and a new one starts
     fn extract_sentences_from_sections(
-        sections: &Vec<SubtitleSection>,
-    ) -> HashMap<u128, Vec<SubtitleSentence>> {
-        //result map stores a vector of SubtitleSentences under the key time_index/1000 -> representing the second the sentence startet
+        sections: &[SubtitleSection],
+        segmenter: &dyn SentenceSegmenter,
+    ) -> SentenceList {
         //the time_index in a SubtitleSentence represents the millisecond in which the sentence startet
-        let mut result: HashMap<u128, Vec<SubtitleSentence>> = HashMap::new();
-        let mut unfinished_sentence: Option<(u128, String)> = None;
-        //for each element in sections
-        for section in sections {
+        let mut result: Vec<SubtitleSentence> = Vec::new();
+        let mut unfinished_sentence: Option<SubtitleSentence> = None;
+        let mut unfinished_turn = 0;
+        //for each utterance in sections
+        for (section, turn, text) in sections.iter().flat_map(|s| s.turns()) {
+            //a sentence never continues after a speaker change
+            if turn != unfinished_turn {
+                if let Some(sentence_from_previous_turn) = unfinished_sentence.take() {
+                    result.push(sentence_from_previous_turn);
+                }
+                unfinished_turn = turn;
+            }
             // probably we should check partial results first. If we have one we just add it to the unfinished_sentence and
             //--extract sentences from its text
-            let extraction_result = SubtitleSection::extract_sentences(&section.text);
+            let extraction_result = segmenter.segment(text, unfinished_sentence.is_some());
             //--if we have a unfinished sentence from the previous section we append a partial or a ending sentence to this unfinished sentence and store it with the previous setion time_index
-            match unfinished_sentence {
-                Some((time_index, sentence_from_previous_section)) => {
-                    //lets check if the current section is a partial
-                    match extraction_result.partial {
-                        //if it is we just connect the sentence_from_previous_section with this partial and store it
-                        //in unfinished_sentence
-                        Some(partial) => {
-                            unfinished_sentence = Some((
-                                time_index,
-                                format!("{} {}", sentence_from_previous_section, partial),
-                            ));
-                            //and we go to the next section
-                            continue;
+            if let Some(mut sentence_from_previous_section) = unfinished_sentence {
+                //lets check if the current section is a partial
+                match extraction_result.partial {
+                    //if it is we just connect the sentence_from_previous_section with this partial and store it
+                    //in unfinished_sentence
+                    Some(partial) => {
+                        sentence_from_previous_section.append(section, &partial);
+                        unfinished_sentence = Some(sentence_from_previous_section);
+                        //and we go to the next section
+                        continue;
+                    }
+                    None => {
+                        //we append before to the previous sentence and store it.
+                        //Without an ending we just store the previous sentence as it is.
+                        if let Some(ending) = extraction_result.end_of_a_sentence {
+                            sentence_from_previous_section.append(section, &ending);
                         }
-                        None => {
-                            let sentence_to_store = match extraction_result.end_of_a_sentence {
-                                //we append before to the previous sentence
-                                //and store it
-                                Some(ending) => {
-                                    format!("{} {}", sentence_from_previous_section, ending)
-                                }
-                                //we just store  previous sentence, but remark this situation
-                                None => {
-                                    println!("We had a previous unfinished sentence but no before and no partial\nPrevious sentence: {}",sentence_from_previous_section);
-                                    sentence_from_previous_section
-                                }
-                            }; //match extraction_result.end_of_a_sentence
-                               //Store the new sentence in our Hashmap
-                            match result.get_mut(&(time_index / 1000)) {
-                                Some(v) => {
-                                    v.push(SubtitleSentence {
-                                        time_index: time_index,
-                                        sentence: sentence_to_store,
-                                    });
-                                }
-                                None => {
-                                    result.insert(
-                                        time_index / 1000,
-                                        vec![SubtitleSentence {
-                                            time_index: time_index,
-                                            sentence: sentence_to_store,
-                                        }],
-                                    );
-                                }
-                            }; //match result.get_mut
-                               //and set None for our unfinished sentence
-                            unfinished_sentence = None;
-                        } //match None arm of extraction_result.partial
-                    } //match extraction_result.partial
-                } //match Some arm of match unfinished_sentence
-                None => (), //unfinished_sentence is already None
+                        //Store the new sentence
+                        result.push(sentence_from_previous_section);
+                        //and set None for our unfinished sentence
+                        unfinished_sentence = None;
+                    } //match None arm of extraction_result.partial
+                } //match extraction_result.partial
+            } else if let Some(ending) = extraction_result.end_of_a_sentence {
+                //without an open sentence, e.g. after a change of speaker, the ending is a sentence on its own
+                result.push(SubtitleSentence::from_section(section, ending));
             }; //unfinished_sentence
                //we don't have an unfinished sentence. lets check if the new section is a partial
-            match extraction_result.partial {
+            if let Some(partial) = extraction_result.partial {
                 //if it is we just store it
                 //in unfinished_sentence
-                Some(partial) => {
-                    unfinished_sentence = Some((section.time_index, format!("{}", partial)));
-                    //and we go to the next section
-                    continue;
-                }
-                None => (), //
+                unfinished_sentence = Some(SubtitleSentence::from_section(section, partial));
+                //and we go to the next section
+                continue;
             }
-            //--we store all sentences @s from this section in our hashmap with the current sections time-index
-            match extraction_result.sentences {
-                Some(sentences) => {
-                    //if we have a sentence and an unfinishe_sentence we store the unfinished sentence and reset it to None
-                    //This situation shouldn't really happen
-                    match unfinished_sentence {
-                        Some((time_index, sentence_from_previous_section)) => {
-                            println!("We had a previous unfinished sentence:\n{}\n but also just found a complete sentence:\n {:?}",sentence_from_previous_section,sentences);
-                            match result.get_mut(&(time_index / 1000)) {
-                                Some(v) => {
-                                    v.push(SubtitleSentence {
-                                        time_index: time_index,
-                                        sentence: sentence_from_previous_section,
-                                    });
-                                }
-                                None => {
-                                    result.insert(
-                                        time_index / 1000,
-                                        vec![SubtitleSentence {
-                                            time_index: time_index,
-                                            sentence: sentence_from_previous_section,
-                                        }],
-                                    );
-                                }
-                            }; //match result.get_mut
-                               //unfinished_sentence = None;we overwrite unfinished_sentence at the end
-                        }
-                        None => (),
-                    }
-                    for s in sentences {
-                        match result.get_mut(&(section.time_index / 1000)) {
-                            Some(v) => v.push(SubtitleSentence {
-                                time_index: section.time_index,
-                                sentence: s,
-                            }),
-                            None => {
-                                result.insert(
-                                    section.time_index / 1000,
-                                    vec![SubtitleSentence {
-                                        time_index: section.time_index,
-                                        sentence: s,
-                                    }],
-                                );
-                            }
-                        };
-                    }
+            //--we store all sentences @s from this section with the current sections time-index
+            if let Some(sentences) = extraction_result.sentences {
+                //if we have a sentence and an unfinishe_sentence we store the unfinished sentence and reset it to None
+                //This situation shouldn't really happen
+                if let Some(sentence_from_previous_section) = unfinished_sentence {
+                    result.push(sentence_from_previous_section);
+                    //unfinished_sentence = None;we overwrite unfinished_sentence at the end
+                }
+                for s in sentences {
+                    result.push(SubtitleSentence::from_section(section, s));
                 }
-                None => (),
             };
             //--and we remember the @s section for the next iteration
-            unfinished_sentence = match extraction_result.begin_of_a_sentence {
-                Some(a) => Some((section.time_index, a)),
-                None => None,
-            };
+            unfinished_sentence = extraction_result
+                .begin_of_a_sentence
+                .map(|a| SubtitleSentence::from_section(section, a));
         } //for section loop
           //lets check if we have a unfinished sentence at the end. We shouldn't have but just in case
           //we add it as a sentence
-        match unfinished_sentence {
-            Some((time_index, after)) => {
-                println!(
-                    "CHECK THIS: We have an after but no more sections...after is:{}",
-                    after
-                );
-                match result.get_mut(&(time_index / 1000)) {
-                    Some(v) => v.push(SubtitleSentence {
-                        time_index: time_index,
-                        sentence: after,
-                    }),
-                    None => {
-                        result.insert(
-                            time_index / 1000,
-                            vec![SubtitleSentence {
-                                time_index: time_index,
-                                sentence: after,
-                            }],
-                        );
-                    }
-                };
-            }
-            None => (),
+        if let Some(after) = unfinished_sentence {
+            result.push(after);
         };
-        result
+        SentenceList::from_vec(result)
     }
     // pub fn from_file_(name: &str, filename: &str) -> Option<Subtitle> {
     //     let contents = fs::read_to_string(filename).expect("Something went wrong reading the file");
@@ -583,19 +739,13 @@ impl Subtitle {
     //         content: sections,
     //     })
     // }
-
-    pub fn to_string(&self) -> String {
-        let text = self.sections.iter().fold(String::new(), |acc, x| {
-            String::from(format!("{}{}", acc, x))
-        });
-        format!("---------{}---------\n{}", self.name, text)
-    }
 }
 impl fmt::Display for Subtitle {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let text = self.sections.iter().fold(String::new(), |acc, x| {
-            String::from(format!("{}{}", acc, x))
-        });
+        let text = self
+            .sections
+            .iter()
+            .fold(String::new(), |acc, x| format!("{}{}", acc, x));
         write!(f, "---------{}---------\n{}", self.name, text)
     }
 }
@@ -613,7 +763,7 @@ mod tests {
         std::fs::write(filename, toml::to_string(&input).unwrap().as_bytes())
     }
     pub fn read_test_set(filename: &str) -> Result<SentenceExtractorTestSet, io::Error> {
-        let content = std::fs::read_to_string(&filename)?;
+        let content = std::fs::read_to_string(filename)?;
         let testset: SentenceExtractorTestSet = toml::from_str(&content).unwrap();
         Ok(testset)
     }
@@ -630,4 +780,292 @@ mod tests {
             );
         }
     }
+
+    const MALFORMED: &str = "1\r\n00:00:01,000 --> 00:00:02,000\r\nHola.\r\n\r\n2\r\n00:00:03 -> 00:00:04\r\nAdiós.\r\n\r\n3\r\n00:00:05,000 --> 00:00:06,000\r\nBuenas noches.\r\n";
+
+    #[test]
+    fn test_empty_file() {
+        let result = Subtitle::from_srt_string("empty", "", ParseMode::Lenient);
+        assert!(matches!(result, Err(SubtitleError::Empty)));
+    }
+
+    #[test]
+    fn test_missing_file() {
+        let result = Subtitle::from_file("missing", "./resources/test/does_not_exist.srt");
+        assert!(matches!(result, Err(SubtitleError::Io(_))));
+    }
+
+    #[test]
+    fn test_strict_mode_reports_position() {
+        match Subtitle::from_srt_string("malformed", MALFORMED, ParseMode::Strict) {
+            Err(SubtitleError::InvalidTimes { block, line, text }) => {
+                assert_eq!(block, 2);
+                assert_eq!(line, 6);
+                assert_eq!(text, "00:00:03 -> 00:00:04");
+            }
+            _ => panic!("expected InvalidTimes"),
+        }
+    }
+
+    #[test]
+    fn test_lenient_mode_collects_warnings() {
+        let subtitle =
+            Subtitle::from_srt_string("malformed", MALFORMED, ParseMode::Lenient).unwrap();
+        assert_eq!(subtitle.sections.len(), 2);
+        assert_eq!(subtitle.sections[1].id, 3);
+        assert_eq!(subtitle.warnings.len(), 1);
+        assert!(matches!(
+            subtitle.warnings[0],
+            SubtitleError::InvalidTimes { block: 2, .. }
+        ));
+    }
+
+    #[test]
+    fn test_load_detects_format() {
+        let subtitle = Subtitle::load("./resources/Subtitles/papel_S01E01_en.srt").unwrap();
+        assert_eq!(subtitle.name, "papel_S01E01_en");
+        assert_eq!(subtitle.format, SubtitleFormat::Srt);
+        assert_eq!(
+            subtitle.source,
+            Some(String::from("./resources/Subtitles/papel_S01E01_en.srt"))
+        );
+        assert_eq!(subtitle.sections[0].text, "Stop or I’ll shoot!");
+        let subtitle = Subtitle::load("./resources/test/test.vtt").unwrap();
+        assert_eq!(subtitle.format, SubtitleFormat::WebVtt);
+        assert_eq!(subtitle.sections.len(), 4);
+        let subtitle = Subtitle::load("./resources/test/test.ass").unwrap();
+        assert_eq!(subtitle.format, SubtitleFormat::Ass);
+        assert_eq!(subtitle.sections.len(), 4);
+    }
+
+    #[test]
+    fn test_load_latin1() {
+        let subtitle = Subtitle::load("./resources/test/latin1.srt").unwrap();
+        assert_eq!(subtitle.encoding, Encoding::Latin1);
+        assert_eq!(subtitle.sections[0].text, "¿Mañana vienes?");
+        assert_eq!(subtitle.sections[1].text, "¡Niño, ven aquí!");
+        let subtitle = Subtitle::load("./resources/Subtitles/papel_S01E01_es.srt").unwrap();
+        assert_eq!(subtitle.encoding, Encoding::Utf8);
+    }
+
+    #[test]
+    fn test_sentences_keep_all_words() {
+        fn words<'a>(texts: impl Iterator<Item = &'a str>) -> Vec<&'a str> {
+            let mut words: Vec<&str> = texts
+                .flat_map(|text| text.split_whitespace())
+                .filter(|word| word.chars().any(char::is_alphanumeric))
+                .collect();
+            words.sort_unstable();
+            words
+        }
+        for name in [
+            "papel_S01E01_es",
+            "papel_S01E01_en",
+            "papel_S01E10_es",
+            "papel_S01E10_en",
+        ] {
+            let subtitle = Subtitle::load(&format!("./resources/Subtitles/{}.srt", name)).unwrap();
+            let section_words = words(subtitle.sections.iter().map(|s| s.text.as_str()));
+            let sentence_words = words(subtitle.sentences.iter().map(|s| s.sentence.as_str()));
+            assert_eq!(sentence_words, section_words, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_write_srt_and_vtt() {
+        let subtitle = Subtitle::load("./resources/Subtitles/papel_S01E01_es.srt").unwrap();
+        for (extension, format) in [
+            ("srt", SubtitleFormat::Srt),
+            ("vtt", SubtitleFormat::WebVtt),
+        ] {
+            let filename = std::env::temp_dir()
+                .join(format!(
+                    "tv_language_trainer_export_{}.{}",
+                    std::process::id(),
+                    extension
+                ))
+                .to_string_lossy()
+                .into_owned();
+            match format {
+                SubtitleFormat::WebVtt => subtitle.write_vtt(&filename).unwrap(),
+                _ => subtitle.write_srt(&filename).unwrap(),
+            }
+            let written = Subtitle::load(&filename).unwrap();
+            std::fs::remove_file(&filename).unwrap();
+            assert_eq!(written.format, format);
+            assert_eq!(written.sections.len(), subtitle.sections.len());
+            for (a, b) in written.sections.iter().zip(subtitle.sections.iter()) {
+                assert_eq!((a.from, a.to), (b.from, b.to));
+                //cues with several lines keep their line breaks
+                assert_eq!(a.raw_lines.len(), b.raw_lines.len(), "{:?}", b.raw_lines);
+                assert_eq!(a.text, b.text);
+            }
+            assert_eq!(written.sections[0].text, "¡Quieto o disparo!");
+        }
+    }
+
+    #[test]
+    fn test_shift_and_rescale() {
+        let mut subtitle =
+            Subtitle::from_srt_string("test", MALFORMED, ParseMode::Lenient).unwrap();
+        assert_eq!(subtitle.sentences[0].time_index, 1000);
+        subtitle.shift(1500);
+        assert_eq!(subtitle.sections[0].from, Duration::from_millis(2500));
+        assert_eq!(subtitle.sections[0].to, Duration::from_millis(3500));
+        assert_eq!(subtitle.sections[1].time_index, 6500);
+        assert_eq!(subtitle.sentences[0].time_index, 2500);
+        assert_eq!(subtitle.sentences[0].start, Duration::from_millis(2500));
+        assert_eq!(subtitle.sentences[0].end, Duration::from_millis(3500));
+        assert_eq!(subtitle.sentences[1].sentence, "Buenas noches.");
+        assert!(subtitle
+            .sentences
+            .sentence_at(Duration::from_secs(2))
+            .is_none());
+        assert_eq!(
+            subtitle
+                .sentences
+                .sentence_at(Duration::from_secs(3))
+                .unwrap()
+                .sentence,
+            "Hola."
+        );
+        assert!(subtitle.rescale(25.0, 24.0));
+        assert_eq!(subtitle.sections[1].from, Duration::from_millis(6771));
+        assert!(!subtitle.rescale(0.0, 25.0));
+        assert!(!subtitle.rescale(25.0, f64::NAN));
+        assert_eq!(subtitle.sections[1].from, Duration::from_millis(6771));
+        assert_eq!(subtitle.sentences[1].time_index, 6771);
+        assert!(subtitle.resync(
+            Duration::from_millis(2604),
+            Duration::from_secs(1),
+            Duration::from_millis(6771),
+            Duration::from_secs(5)
+        ));
+        assert_eq!(subtitle.sections[0].from, Duration::from_secs(1));
+        assert_eq!(subtitle.sentences[1].start, Duration::from_secs(5));
+        assert!(!subtitle.resync(
+            Duration::ZERO,
+            Duration::ZERO,
+            Duration::ZERO,
+            Duration::ZERO
+        ));
+    }
+
+    #[test]
+    fn test_sentence_provenance() {
+        let subtitle = Subtitle::from_srt_string(
+            "test",
+            "1\n00:00:01,000 --> 00:00:02,000\nHola. Me llamo\n\n2\n00:00:02,500 --> 00:00:03,000\nRaquel y soy\n\n3\n00:00:03,500 --> 00:00:05,000\ninspectora. ¿Y tú?\n",
+            ParseMode::Strict,
+        )
+        .unwrap();
+        let hola = &subtitle.sentences[0];
+        assert_eq!(hola.sentence, "Hola.");
+        assert_eq!(hola.section_ids, vec![1]);
+        assert_eq!(
+            (hola.start, hola.end),
+            (Duration::from_secs(1), Duration::from_secs(2))
+        );
+        let joined = &subtitle.sentences[1];
+        assert_eq!(joined.sentence, "Me llamo Raquel y soy inspectora.");
+        assert_eq!(joined.section_ids, vec![1, 2, 3]);
+        assert_eq!(joined.time_index, 1000);
+        assert_eq!(joined.start, Duration::from_secs(1));
+        assert_eq!(joined.end, Duration::from_secs(5));
+        let tu = &subtitle.sentences[2];
+        assert_eq!(tu.sentence, "¿Y tú?");
+        assert_eq!(tu.section_ids, vec![3]);
+        assert_eq!(tu.start, Duration::from_millis(3500));
+    }
+
+    #[test]
+    fn test_load_unknown_format() {
+        let result = Subtitle::load("./resources/test/sentence_extractor.toml");
+        assert!(matches!(result, Err(SubtitleError::UnknownFormat { .. })));
+    }
+
+    #[test]
+    fn test_resegment() {
+        let contents = "1\n00:00:01,000 --> 00:00:02,000\nIch habe z.B. das\n\n2\n00:00:03,000 --> 00:00:04,000\nHaus gesehen. Und du?\n";
+        let mut subtitle = Subtitle::from_srt_string("de", contents, ParseMode::Strict).unwrap();
+        assert_eq!(subtitle.sentences.len(), 3);
+        subtitle.resegment(&RuleSegmenter::for_language(
+            &crate::flashcard::Language::German,
+        ));
+        assert_eq!(subtitle.sentences.len(), 2);
+        assert_eq!(
+            subtitle.sentences[0].sentence,
+            "Ich habe z.B. das Haus gesehen."
+        );
+        assert_eq!(subtitle.sentences[0].section_ids, vec![1, 2]);
+        assert_eq!(subtitle.sentences[1].sentence, "Und du?");
+    }
+
+    #[test]
+    fn test_sentences_stop_at_turns() {
+        let contents = "1\n00:00:01,000 --> 00:00:02,000\n- ¿Vienes? - Yo quería\n\n2\n00:00:03,000 --> 00:00:04,000\n- Vale, pero\n\n3\n00:00:05,000 --> 00:00:06,000\nrápido.\n";
+        let subtitle = Subtitle::from_srt_string("turns", contents, ParseMode::Strict).unwrap();
+        let turns: Vec<usize> = subtitle
+            .sections
+            .iter()
+            .flat_map(|s| s.utterances.iter().map(|u| u.turn))
+            .collect();
+        assert_eq!(turns, vec![0, 1, 2, 2]);
+        let sentences: Vec<&str> = subtitle
+            .sentences
+            .iter()
+            .map(|s| s.sentence.as_str())
+            .collect();
+        assert_eq!(
+            sentences,
+            vec!["¿Vienes?", "Yo quería", "Vale, pero rápido."]
+        );
+        assert_eq!(subtitle.sentences[2].section_ids, vec![2, 3]);
+    }
+
+    #[test]
+    fn test_annotations() {
+        let contents = "1\n00:00:01,000 --> 00:00:02,000\n♪ Bella ciao ♪\n\n2\n00:00:03,000 --> 00:00:04,000\n(Disparo)\n(NAIROBI) ¡Al suelo!\n";
+        let subtitle = Subtitle::from_srt_string("hi", contents, ParseMode::Strict).unwrap();
+        assert_eq!(subtitle.sections.len(), 2);
+        assert_eq!(subtitle.sections[0].text, "");
+        assert_eq!(subtitle.sections[1].text, "¡Al suelo!");
+        assert_eq!(subtitle.sections[1].speaker, Some(String::from("NAIROBI")));
+        let lyrics = subtitle.annotations(AnnotationKind::Lyrics);
+        assert_eq!(lyrics.len(), 1);
+        assert_eq!(lyrics[0].0.id, 1);
+        assert_eq!(lyrics[0].1.text, "Bella ciao");
+        assert_eq!(subtitle.annotations(AnnotationKind::SoundEffect).len(), 1);
+        assert_eq!(subtitle.sentences.len(), 1);
+        assert!(subtitle.to_srt_string().contains("\n♪ Bella ciao ♪\n"));
+        let mut subtitle = subtitle;
+        subtitle.resegment_with(&RuleSegmenter::new(), true);
+        let sentences: Vec<&str> = subtitle
+            .sentences
+            .iter()
+            .map(|s| s.sentence.as_str())
+            .collect();
+        assert_eq!(sentences, vec!["Bella ciao", "¡Al suelo!"]);
+        assert_eq!(subtitle.sentences[0].section_ids, vec![1]);
+    }
+
+    #[test]
+    fn test_text_line() {
+        let mut section = SubtitleSection::new();
+        section.add_text("¡Quieto o disparo!");
+        assert_eq!(section.text_line(), "¡Quieto o disparo!\n");
+    }
+
+    #[test]
+    fn test_invalid_id() {
+        match SubtitleSection::from_string(String::from(
+            "x1\r\n00:00:01,000 --> 00:00:02,000\r\nHola.",
+        )) {
+            Err(SubtitleError::InvalidId { block, line, text }) => {
+                assert_eq!((block, line), (1, 1));
+                assert_eq!(text, "x1");
+            }
+            _ => panic!("expected InvalidId"),
+        }
+    }
 }
@@ -0,0 +1,272 @@
+use super::{Subtitle, SubtitleSentence};
+use std::time::Duration;
+
+///Number of sentences on each side of an `AlignedSentences`
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum AlignmentKind {
+    OneToOne,
+    OneToTwo,
+    TwoToOne,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct AlignmentOptions {
+    ///the time spans of the sentences are extended by `tolerance` before they are compared.
+    ///Sentences whose extended spans don't overlap are never aligned.
+    pub tolerance: Duration,
+    ///expected number of secondary characters per primary character
+    pub length_ratio: f64,
+}
+
+impl Default for AlignmentOptions {
+    fn default() -> Self {
+        AlignmentOptions::new()
+    }
+}
+
+impl AlignmentOptions {
+    pub fn new() -> AlignmentOptions {
+        AlignmentOptions {
+            tolerance: Duration::from_millis(500),
+            length_ratio: 1.0,
+        }
+    }
+}
+
+///Sentences of the two tracks which are translations of each other
+#[derive(Debug)]
+pub struct AlignedSentences<'a> {
+    pub primary: Vec<&'a SubtitleSentence>,
+    pub secondary: Vec<&'a SubtitleSentence>,
+    pub kind: AlignmentKind,
+    ///between 0 and 1, the geometric mean of the time overlap and the length score
+    pub confidence: f64,
+}
+
+#[derive(Debug)]
+pub struct Alignment<'a> {
+    ///aligned sentences ordered by time
+    pub pairs: Vec<AlignedSentences<'a>>,
+    pub unaligned_primary: Vec<&'a SubtitleSentence>,
+    pub unaligned_secondary: Vec<&'a SubtitleSentence>,
+}
+
+///A sentence with the time span it is shown
+struct Span<'a> {
+    sentence: &'a SubtitleSentence,
+    from: u128,
+    to: u128,
+    length: usize,
+}
+
+///The possible steps through both tracks as (primary sentences, secondary sentences)
+///with their prior probability according to Gale and Church
+const MOVES: [(usize, usize, f64); 5] = [
+    (1, 1, 0.89),
+    (1, 2, 0.089),
+    (2, 1, 0.089),
+    (1, 0, 0.0099),
+    (0, 1, 0.0099),
+];
+
+///Variance of the length difference per character (Gale and Church)
+const LENGTH_VARIANCE: f64 = 6.8;
+
+impl<'a> Alignment<'a> {
+    ///Aligns the sentences of the two tracks with dynamic programming. The cost of aligning sentences
+    ///combines the overlap of their time spans with the length based score of Gale and Church.
+    pub fn align(
+        primary: &'a Subtitle,
+        secondary: &'a Subtitle,
+        options: &AlignmentOptions,
+    ) -> Alignment<'a> {
+        let primary = spans(primary);
+        let secondary = spans(secondary);
+        let (n, m) = (primary.len(), secondary.len());
+        //cost[i][j] is the lowest cost to align the first i primary with the first j secondary sentences
+        let mut cost = vec![vec![f64::INFINITY; m + 1]; n + 1];
+        let mut previous = vec![vec![(0, 0, 0.0); m + 1]; n + 1];
+        cost[0][0] = 0.0;
+        for i in 0..=n {
+            for j in 0..=m {
+                if cost[i][j].is_infinite() {
+                    continue;
+                }
+                for (di, dj, prior) in MOVES.iter() {
+                    let (ni, nj) = (i + di, j + dj);
+                    if ni > n || nj > m {
+                        continue;
+                    }
+                    let (step, confidence) = if *di == 0 || *dj == 0 {
+                        (-prior.ln(), 0.0)
+                    } else {
+                        match score(&primary[i..ni], &secondary[j..nj], options) {
+                            Some((time, length)) => (
+                                -prior.ln() - time.ln() - length.ln(),
+                                (time * length).sqrt(),
+                            ),
+                            None => continue,
+                        }
+                    };
+                    if cost[i][j] + step < cost[ni][nj] {
+                        cost[ni][nj] = cost[i][j] + step;
+                        previous[ni][nj] = (*di, *dj, confidence);
+                    }
+                }
+            }
+        }
+        //follow the cheapest path back from the end
+        let mut alignment = Alignment {
+            pairs: Vec::new(),
+            unaligned_primary: Vec::new(),
+            unaligned_secondary: Vec::new(),
+        };
+        let (mut i, mut j) = (n, m);
+        while i > 0 || j > 0 {
+            let (di, dj, confidence) = previous[i][j];
+            let primary_sentences: Vec<&SubtitleSentence> =
+                primary[i - di..i].iter().map(|s| s.sentence).collect();
+            let secondary_sentences: Vec<&SubtitleSentence> =
+                secondary[j - dj..j].iter().map(|s| s.sentence).collect();
+            match (di, dj) {
+                (_, 0) => alignment.unaligned_primary.extend(primary_sentences),
+                (0, _) => alignment.unaligned_secondary.extend(secondary_sentences),
+                _ => alignment.pairs.push(AlignedSentences {
+                    primary: primary_sentences,
+                    secondary: secondary_sentences,
+                    kind: match (di, dj) {
+                        (1, 2) => AlignmentKind::OneToTwo,
+                        (2, 1) => AlignmentKind::TwoToOne,
+                        _ => AlignmentKind::OneToOne,
+                    },
+                    confidence,
+                }),
+            }
+            i -= di;
+            j -= dj;
+        }
+        alignment.pairs.reverse();
+        alignment.unaligned_primary.reverse();
+        alignment.unaligned_secondary.reverse();
+        alignment
+    }
+}
+
+///Returns the time score and the length score of aligning the sentences,
+///or None if their time spans don't overlap
+fn score(primary: &[Span], secondary: &[Span], options: &AlignmentOptions) -> Option<(f64, f64)> {
+    let tolerance = options.tolerance.as_millis();
+    let extent = |spans: &[Span]| {
+        (
+            spans[0].from.saturating_sub(tolerance),
+            spans[spans.len() - 1].to + tolerance,
+        )
+    };
+    let (a_from, a_to) = extent(primary);
+    let (b_from, b_to) = extent(secondary);
+    let intersection = a_to.min(b_to).saturating_sub(a_from.max(b_from));
+    if intersection == 0 {
+        return None;
+    }
+    let union = a_to.max(b_to) - a_from.min(b_from);
+    let time = intersection as f64 / union as f64;
+    let length = |spans: &[Span]| spans.iter().map(|s| s.length).sum::<usize>() as f64;
+    Some((
+        time,
+        length_probability(length(primary), length(secondary), options.length_ratio),
+    ))
+}
+
+///Probability that a text of `primary` characters is translated into `secondary` characters (Gale and Church)
+fn length_probability(primary: f64, secondary: f64, ratio: f64) -> f64 {
+    let mean = (primary + secondary / ratio) / 2.0;
+    if mean == 0.0 {
+        return 1.0;
+    }
+    let delta = (secondary - primary * ratio) / (mean * LENGTH_VARIANCE).sqrt();
+    (2.0 * (1.0 - normal_cdf(delta.abs()))).max(0.001)
+}
+
+fn normal_cdf(x: f64) -> f64 {
+    0.5 * (1.0 + erf(x / std::f64::consts::SQRT_2))
+}
+
+///Approximation of Abramowitz and Stegun with a maximal error of 1.5e-7
+fn erf(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.3275911 * x.abs());
+    let polynomial = t
+        * (0.254829592
+            + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    let result = 1.0 - polynomial * (-x * x).exp();
+    if x < 0.0 {
+        -result
+    } else {
+        result
+    }
+}
+
+fn spans(subtitle: &Subtitle) -> Vec<Span<'_>> {
+    subtitle
+        .sentences
+        .iter()
+        .map(|sentence| Span {
+            sentence,
+            from: sentence.time_index,
+            //sentences stored before they had an end are treated as a point in time
+            to: sentence.end.as_millis().max(sentence.time_index),
+            length: sentence.sentence.chars().count(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subtitle::ParseMode;
+
+    fn texts(sentences: &[&SubtitleSentence]) -> Vec<String> {
+        sentences.iter().map(|s| s.sentence.clone()).collect()
+    }
+
+    #[test]
+    fn test_align() {
+        let es = Subtitle::from_srt_string(
+            "es",
+            "1\n00:00:01,000 --> 00:00:03,000\n¿Vienes a la fiesta esta noche?\n\n2\n00:00:04,000 --> 00:00:07,000\nNo puedo, tengo que trabajar hasta muy tarde.\n\n3\n00:00:08,000 --> 00:00:09,000\nVale.\n\n4\n00:00:30,000 --> 00:00:31,000\n¡Corre!\n",
+            ParseMode::Strict,
+        )
+        .unwrap();
+        //the english track starts 300ms later, splits the second sentence and misses the last one
+        let en = Subtitle::from_srt_string(
+            "en",
+            "1\n00:00:01,300 --> 00:00:03,300\nAre you coming to the party tonight?\n\n2\n00:00:04,300 --> 00:00:05,500\nI can't.\n\n3\n00:00:05,600 --> 00:00:07,300\nI have to work very late.\n\n4\n00:00:08,300 --> 00:00:09,300\nOkay.\n\n5\n00:00:20,000 --> 00:00:21,000\nHey!\n",
+            ParseMode::Strict,
+        )
+        .unwrap();
+        let alignment = Alignment::align(&es, &en, &AlignmentOptions::new());
+        assert_eq!(alignment.pairs.len(), 3);
+        assert_eq!(alignment.pairs[0].kind, AlignmentKind::OneToOne);
+        assert_eq!(
+            texts(&alignment.pairs[0].secondary),
+            vec!["Are you coming to the party tonight?"]
+        );
+        assert!(alignment.pairs[0].confidence > 0.5);
+        assert_eq!(alignment.pairs[1].kind, AlignmentKind::OneToTwo);
+        assert_eq!(
+            texts(&alignment.pairs[1].secondary),
+            vec!["I can't.", "I have to work very late."]
+        );
+        assert_eq!(texts(&alignment.pairs[2].primary), vec!["Vale."]);
+        assert_eq!(texts(&alignment.pairs[2].secondary), vec!["Okay."]);
+        assert_eq!(texts(&alignment.unaligned_primary), vec!["¡Corre!"]);
+        assert_eq!(texts(&alignment.unaligned_secondary), vec!["Hey!"]);
+    }
+
+    #[test]
+    fn test_length_probability() {
+        assert!(length_probability(40.0, 40.0, 1.0) > 0.99);
+        assert!(length_probability(40.0, 44.0, 1.0) > length_probability(40.0, 60.0, 1.0));
+        assert!(length_probability(40.0, 10.0, 1.0) < 0.05);
+        assert!((erf(1.0) - 0.842_700_79).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,200 @@
+use crate::toolbox;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+lazy_static! {
+    //[DOOR SLAMS] or (laughs). Longer parentheses are asides of the dialogue.
+    static ref SOUND_EFFECT_REGEX: Regex =
+        Regex::new(r#"\[([^\]]{1,30})\]|\(([^)]{1,30})\)"#).unwrap();
+    //(BERLÍN) or [TOKYO] in front of the dialogue. Only single words, as "[DOOR SLAMS]" is a sound effect.
+    static ref BRACKETED_LABEL_REGEX: Regex =
+        Regex::new(r#"^([-–—]\s*)?[\[(](\p{Lu}[\p{Lu}\d.'’-]*)[\])]\s*"#).unwrap();
+    //TOKYO: at the start of a line, possibly after a dialogue dash
+    static ref SPEAKER_LABEL_REGEX: Regex =
+        Regex::new(r#"^([-–—]\s*)?(\p{Lu}[\p{Lu}\d .'’-]{0,29}):(?:\s+|$)"#).unwrap();
+    static ref LYRICS_REGEX: Regex = Regex::new(r#"^[-–—]?\s*[♪♫#]"#).unwrap();
+}
+
+///Text of a cue which isn't spoken dialogue, as found in subtitles for the hearing impaired
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnnotationKind {
+    ///description of a sound like "[DOOR SLAMS]" or "(laughs)"
+    SoundEffect,
+    ///name of the speaker in front of the line like "TOKYO:"
+    SpeakerLabel,
+    ///lines of a song marked with ♪
+    Lyrics,
+    ///text shown in the picture, written in capitals like "SUSPECT'S WHEREABOUTS UNKNOWN"
+    OnScreenText,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Annotation {
+    pub kind: AnnotationKind,
+    ///the text without brackets, colon or music notes
+    pub text: String,
+}
+
+impl Annotation {
+    pub fn new(kind: AnnotationKind, text: &str) -> Annotation {
+        Annotation {
+            kind,
+            text: String::from(text.trim()),
+        }
+    }
+}
+
+///Writes the annotation the way it appears in a subtitle file
+impl fmt::Display for Annotation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            AnnotationKind::SoundEffect => write!(f, "[{}]", self.text),
+            AnnotationKind::SpeakerLabel => write!(f, "{}:", self.text),
+            AnnotationKind::Lyrics => write!(f, "♪ {} ♪", self.text),
+            AnnotationKind::OnScreenText => write!(f, "{}", self.text),
+        }
+    }
+}
+
+///Removes the markup and the annotations from the text lines of a cue and returns the annotations.
+///A speaker label becomes the speaker of its line if the format didn't name one.
+///A single word in capitals in brackets is taken for a speaker label if dialogue follows, otherwise for a sound effect.
+pub fn extract(lines: &mut [(Option<String>, String)]) -> Vec<Annotation> {
+    let mut annotations = Vec::new();
+    let cleaned: Vec<String> = lines
+        .iter()
+        .map(|(_, line)| toolbox::clean_content_string(line))
+        .collect();
+    //a label on a line of its own names the speaker of the next line
+    let mut pending_label: Option<String> = None;
+    for (i, (speaker, line)) in lines.iter_mut().enumerate() {
+        if speaker.is_none() {
+            *speaker = pending_label.take();
+        }
+        let mut text = cleaned[i].trim().to_string();
+        if let Some(caps) = BRACKETED_LABEL_REGEX.captures(&text) {
+            let rest = &text[caps.get(0).unwrap().end()..];
+            let next_line = cleaned.get(i + 1).map(|l| l.trim()).unwrap_or("");
+            let dialogue = |text: &str| !text.is_empty() && !text.starts_with(['(', '[']);
+            if dialogue(rest) || (rest.is_empty() && dialogue(next_line)) {
+                let label = caps[2].to_string();
+                annotations.push(Annotation::new(AnnotationKind::SpeakerLabel, &label));
+                if speaker.is_none() {
+                    *speaker = Some(label.clone());
+                }
+                if rest.is_empty() {
+                    pending_label = Some(label);
+                }
+                text = BRACKETED_LABEL_REGEX.replace(&text, "$1").into_owned();
+            }
+        }
+        for caps in SOUND_EFFECT_REGEX.captures_iter(&text) {
+            let effect = caps.get(1).or_else(|| caps.get(2)).unwrap().as_str();
+            annotations.push(Annotation::new(AnnotationKind::SoundEffect, effect));
+        }
+        text = SOUND_EFFECT_REGEX.replace_all(&text, "").trim().to_string();
+        if let Some(caps) = SPEAKER_LABEL_REGEX.captures(&text) {
+            let label = caps[2].trim().to_string();
+            annotations.push(Annotation::new(AnnotationKind::SpeakerLabel, &label));
+            if speaker.is_none() {
+                *speaker = Some(label);
+            }
+            text = SPEAKER_LABEL_REGEX.replace(&text, "$1").into_owned();
+        }
+        if LYRICS_REGEX.is_match(&text) {
+            let lyrics = text.trim_matches(|c: char| "♪♫#-–— ".contains(c));
+            if !lyrics.is_empty() {
+                annotations.push(Annotation::new(AnnotationKind::Lyrics, lyrics));
+            }
+            text.clear();
+        } else if is_on_screen_text(&text) {
+            annotations.push(Annotation::new(AnnotationKind::OnScreenText, &text));
+            text.clear();
+        }
+        *line = text;
+    }
+    annotations
+}
+
+///Lines of several words in capitals without the punctuation of shouted dialogue
+fn is_on_screen_text(text: &str) -> bool {
+    let letters = text.chars().filter(|c| c.is_alphabetic()).count();
+    letters >= 6
+        && text.split_whitespace().count() >= 2
+        && !text.chars().any(|c| c.is_lowercase() || "!?¡¿".contains(c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extract_line(line: &str) -> (Option<String>, String, Vec<Annotation>) {
+        let mut lines = vec![(None, String::from(line))];
+        let annotations = extract(&mut lines);
+        let (speaker, text) = lines.remove(0);
+        (speaker, text, annotations)
+    }
+
+    #[test]
+    fn test_sound_effects() {
+        let (_, text, annotations) = extract_line("[PUERTA SE CIERRA] ¿Quién es? (susurrando)");
+        assert_eq!(text, "¿Quién es?");
+        assert_eq!(
+            annotations,
+            vec![
+                Annotation::new(AnnotationKind::SoundEffect, "PUERTA SE CIERRA"),
+                Annotation::new(AnnotationKind::SoundEffect, "susurrando")
+            ]
+        );
+        let aside = "Lo sabía (y no se lo dije a nadie, ni siquiera a mi madre).";
+        let (_, text, annotations) = extract_line(aside);
+        assert_eq!(text, aside);
+        assert!(annotations.is_empty());
+    }
+
+    #[test]
+    fn test_speaker_label() {
+        let (speaker, text, annotations) = extract_line("- TOKYO: Me llamo Tokio.");
+        assert_eq!(speaker, Some(String::from("TOKYO")));
+        assert_eq!(text, "- Me llamo Tokio.");
+        assert_eq!(annotations[0].kind, AnnotationKind::SpeakerLabel);
+        let mut lines = vec![
+            (None, String::from("(BERLÍN)")),
+            (None, String::from("Con un arma en la mano.")),
+            (None, String::from("-(TOSE)")),
+        ];
+        let annotations = extract(&mut lines);
+        assert_eq!(lines[0].1, "");
+        assert_eq!(lines[1].0, Some(String::from("BERLÍN")));
+        assert_eq!(
+            annotations,
+            vec![
+                Annotation::new(AnnotationKind::SpeakerLabel, "BERLÍN"),
+                Annotation::new(AnnotationKind::SoundEffect, "TOSE")
+            ]
+        );
+        let (speaker, text, _) = extract_line("Son las 10:30.");
+        assert_eq!(speaker, None);
+        assert_eq!(text, "Son las 10:30.");
+    }
+
+    #[test]
+    fn test_lyrics_and_on_screen_text() {
+        let (_, text, annotations) = extract_line("<i>♪ Bella ciao, bella ciao ♪</i>");
+        assert_eq!(text, "");
+        assert_eq!(
+            annotations,
+            vec![Annotation::new(
+                AnnotationKind::Lyrics,
+                "Bella ciao, bella ciao"
+            )]
+        );
+        let (_, text, annotations) = extract_line("SUSPECT'S WHEREABOUTS UNKNOWN");
+        assert_eq!(text, "");
+        assert_eq!(annotations[0].kind, AnnotationKind::OnScreenText);
+        let (_, text, annotations) = extract_line("¡NO ME DEJES!");
+        assert_eq!(text, "¡NO ME DEJES!");
+        assert!(annotations.is_empty());
+    }
+}
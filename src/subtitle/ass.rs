@@ -0,0 +1,325 @@
+use super::colour::Colour;
+use super::{annotations, markup, turns, ParseMode, SubtitleError, SubtitleSection};
+use crate::toolbox;
+use regex::Regex;
+use std::time::Duration;
+
+lazy_static! {
+    //H:MM:SS.cc with centiseconds
+    static ref ASS_TIME_REGEX: Regex = Regex::new(r#"^(\d+):(\d{2}):(\d{2})[\.:](\d{2})$"#).unwrap();
+    //drawing commands between {\p1} and {\p0} are vector graphics and not text
+    static ref DRAWING_REGEX: Regex =
+        Regex::new(r#"\{[^}]*\\p[1-9][^}]*\}.*?(\{[^}]*\\p0[^}]*\}|$)"#).unwrap();
+}
+
+///Column order of the `[Events]` section if the file doesn't contain a `Format:` line
+const DEFAULT_FORMAT: [&str; 10] = [
+    "Layer", "Start", "End", "Style", "Name", "MarginL", "MarginR", "MarginV", "Effect", "Text",
+];
+
+///Parses the `Dialogue:` lines of the `[Events]` section of an ASS/SSA file into sections sorted by start time.
+///The second element contains the skipped events in `ParseMode::Lenient`.
+pub fn parse(
+    contents: &str,
+    mode: ParseMode,
+) -> Result<(Vec<SubtitleSection>, Vec<SubtitleError>), SubtitleError> {
+    let contents = contents.trim_start_matches('\u{feff}');
+    let mut in_events = false;
+    let mut format: Vec<String> = DEFAULT_FORMAT.iter().map(|c| String::from(*c)).collect();
+    let mut sections = Vec::new();
+    let mut warnings = Vec::new();
+    let mut dialogue_number = 0;
+    for (i, line) in toolbox::split_lines(contents).into_iter().enumerate() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_events = line.eq_ignore_ascii_case("[Events]");
+            continue;
+        }
+        if !in_events {
+            continue;
+        }
+        if let Some(columns) = line.strip_prefix("Format:") {
+            format = columns.split(',').map(|c| String::from(c.trim())).collect();
+        } else if let Some(values) = line.strip_prefix("Dialogue:") {
+            dialogue_number += 1;
+            match parse_dialogue(values, &format, dialogue_number, i + 1) {
+                Ok(Some(s)) => sections.push(s),
+                Ok(None) => (),
+                Err(e) => match mode {
+                    ParseMode::Strict => return Err(e),
+                    ParseMode::Lenient => warnings.push(e),
+                },
+            }
+        }
+    }
+    if dialogue_number == 0 {
+        return Err(SubtitleError::Empty);
+    }
+    //events don't have to be in chronological order, the ids follow the sorted order
+    sections.sort_by_key(|s| s.from);
+    for (i, section) in sections.iter_mut().enumerate() {
+        section.id = i as u64 + 1;
+    }
+    Ok((sections, warnings))
+}
+
+fn parse_dialogue(
+    values: &str,
+    format: &[String],
+    dialogue_number: usize,
+    line: usize,
+) -> Result<Option<SubtitleSection>, SubtitleError> {
+    //the text is always the last column and can contain commas
+    let values: Vec<&str> = values.splitn(format.len(), ',').collect();
+    let column = |name: &str| {
+        format
+            .iter()
+            .position(|c| c.eq_ignore_ascii_case(name))
+            .and_then(|i| values.get(i))
+            .map(|v| v.trim())
+    };
+    let invalid_dialogue = || SubtitleError::InvalidDialogue {
+        block: dialogue_number,
+        line,
+        text: values.join(","),
+    };
+    let (start, end, text) = match (column("Start"), column("End"), column("Text")) {
+        (Some(start), Some(end), Some(text)) => (start, end, text),
+        _ => return Err(invalid_dialogue()),
+    };
+    let invalid_times = || SubtitleError::InvalidTimes {
+        block: dialogue_number,
+        line,
+        text: format!("{},{}", start, end),
+    };
+    let from = get_time(start).ok_or_else(invalid_times)?;
+    let to = get_time(end).ok_or_else(invalid_times)?;
+    let raw_lines: Vec<String> = text
+        .replace("\\n", "\\N")
+        .split("\\N")
+        .map(|line| String::from(line.trim()))
+        .collect();
+    let non_empty = |v: Option<&str>| v.filter(|v| !v.is_empty()).map(String::from);
+    let speaker = non_empty(column("Name"));
+    let mut text_lines: Vec<(Option<String>, String)> = split_text(text)
+        .into_iter()
+        .map(|line| (speaker.clone(), line))
+        .collect();
+    let annotations = annotations::extract(&mut text_lines);
+    let utterances = turns::split(&text_lines);
+    if utterances.is_empty() && annotations.is_empty() {
+        return Ok(None);
+    }
+    let mut section = SubtitleSection::new();
+    section.from = from;
+    section.to = to;
+    section.time_index = from.as_millis();
+    section.text = turns::text(&utterances);
+    section.utterances = utterances;
+    section.annotations = annotations;
+    let (spans, position) = markup::parse(&raw_lines);
+    section.raw_lines = raw_lines;
+    section.set_spans(spans);
+    section.position = position;
+    section.speaker = speaker;
+    section.style = non_empty(column("Style"));
+    Ok(Some(section))
+}
+
+///Parses `H:MM:SS.cc`. Returns None if the hours are too large.
+pub fn get_time(time: &str) -> Option<Duration> {
+    let caps = ASS_TIME_REGEX.captures(time)?;
+    let number = |i: usize| caps[i].parse::<u64>().ok();
+    let millis = number(1)?
+        .checked_mul(60)?
+        .checked_add(number(2)?)?
+        .checked_mul(60)?
+        .checked_add(number(3)?)?
+        .checked_mul(1000)?
+        .checked_add(number(4)? * 10)?;
+    Some(Duration::from_millis(millis))
+}
+
+///Formats the duration as `H:MM:SS.cc`
+pub fn format_time(time: Duration) -> String {
+    let centis = time.as_millis() / 10;
+    format!(
+        "{}:{:02}:{:02}.{:02}",
+        centis / 360_000,
+        centis / 6000 % 60,
+        centis / 100 % 60,
+        centis % 100
+    )
+}
+
+///Style of the `[V4+ Styles]` section written by `write`
+pub struct Style {
+    pub name: String,
+    pub colour: Colour,
+    pub italic: bool,
+}
+
+///Writes the sections as ASS file. Sections without style use the first of `styles`.
+///Line breaks in the text are written as `\N`, the text can contain override blocks.
+pub fn write(title: &str, styles: &[Style], sections: &[SubtitleSection]) -> String {
+    let mut result = format!(
+        "[Script Info]\nTitle: {}\nScriptType: v4.00+\nWrapStyle: 0\nScaledBorderAndShadow: yes\nPlayResX: 384\nPlayResY: 288\n\n[V4+ Styles]\nFormat: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, OutlineColour, BackColour, Bold, Italic, Underline, StrikeOut, ScaleX, ScaleY, Spacing, Angle, BorderStyle, Outline, Shadow, Alignment, MarginL, MarginR, MarginV, Encoding\n",
+        title
+    );
+    for style in styles {
+        result.push_str(&format!(
+            "Style: {},Arial,16,{},&H000000FF,&H00000000,&H80000000,0,{},0,0,100,100,0,0,1,1,1,2,10,10,10,1\n",
+            style.name,
+            style.colour.to_ass(),
+            if style.italic { -1 } else { 0 }
+        ));
+    }
+    result.push_str(
+        "\n[Events]\nFormat: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n",
+    );
+    let default_style = styles.first().map(|s| s.name.as_str()).unwrap_or("Default");
+    let mut sorted: Vec<&SubtitleSection> = sections.iter().collect();
+    sorted.sort_by_key(|s| s.from);
+    for section in sorted {
+        result.push_str(&format!(
+            "Dialogue: 0,{},{},{},{},0,0,0,,{}\n",
+            format_time(section.from),
+            format_time(section.to),
+            section.style.as_deref().unwrap_or(default_style),
+            section.speaker.as_deref().unwrap_or(""),
+            match section.export_spans() {
+                Some(spans) => markup::to_ass(spans, section.position),
+                None => section.export_text().replace('\n', "\\N"),
+            }
+        ));
+    }
+    result
+}
+
+///Removes drawings, turns the `\N` and `\n` line breaks into lines and `\h` into a space.
+///The override blocks like `{\i1}` are removed by `toolbox::clean_content_string`.
+fn split_text(text: &str) -> Vec<String> {
+    DRAWING_REGEX
+        .replace_all(text, "")
+        .replace("\\h", " ")
+        .replace("\\n", "\\N")
+        .split("\\N")
+        .map(|line| String::from(line.trim()))
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subtitle::{Annotation, AnnotationKind};
+
+    #[test]
+    fn test_ass_file() {
+        let contents = std::fs::read_to_string("./resources/test/test.ass").unwrap();
+        let (sections, warnings) = parse(&contents, ParseMode::Strict).unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(sections.len(), 4);
+        //sorted by start time and numbered in that order
+        let ids: Vec<u64> = sections.iter().map(|s| s.id).collect();
+        assert_eq!(ids, vec![1, 2, 3, 4]);
+        assert_eq!(sections[0].from, Duration::from_millis(6520));
+        assert_eq!(sections[0].to, Duration::from_millis(7760));
+        assert_eq!(sections[0].text, "¡Quieto o disparo!");
+        assert_eq!(sections[0].style, Some(String::from("Default")));
+        assert_eq!(sections[0].speaker, None);
+        assert_eq!(sections[1].text, "Me llamo Tokio, y esta es mi historia.");
+        assert_eq!(sections[1].speaker, Some(String::from("Tokio")));
+        assert_eq!(
+            sections[1].raw_lines,
+            vec!["{\\i1}Me llamo Tokio,{\\i0}", "y esta es mi historia."]
+        );
+        assert_eq!(sections[2].style, Some(String::from("Sign")));
+        assert_eq!(sections[2].text, "");
+        assert_eq!(
+            sections[2].annotations,
+            vec![Annotation::new(
+                AnnotationKind::OnScreenText,
+                "BANCO DE ESPAÑA"
+            )]
+        );
+        assert_eq!(sections[3].style, Some(String::from("Song")));
+        assert_eq!(sections[3].text, "Bella ciao, bella ciao");
+    }
+
+    #[test]
+    fn test_write() {
+        let contents = std::fs::read_to_string("./resources/test/test.ass").unwrap();
+        let (mut sections, _) = parse(&contents, ParseMode::Strict).unwrap();
+        sections[0].text = String::from("¡Quieto\no disparo!");
+        let styles = [Style {
+            name: String::from("Default"),
+            colour: Colour::new(255, 255, 255),
+            italic: false,
+        }];
+        let written = write("test", &styles, &sections);
+        assert!(written.contains("Style: Default,Arial,16,&H00FFFFFF,"));
+        assert!(written
+            .contains("Dialogue: 0,0:00:06.52,0:00:07.76,Default,,0,0,0,,¡Quieto\\No disparo!\n"));
+        assert!(written.contains("Dialogue: 0,0:00:25.20,0:00:26.40,Default,Tokio,"));
+        let (written_sections, _) = parse(&written, ParseMode::Strict).unwrap();
+        assert_eq!(written_sections.len(), 4);
+        assert_eq!(written_sections[0].text, "¡Quieto o disparo!");
+        assert_eq!(written_sections[3].style, Some(String::from("Song")));
+        assert_eq!(
+            format_time(Duration::from_millis(36_061_239)),
+            "10:01:01.23"
+        );
+    }
+
+    #[test]
+    fn test_ssa_format() {
+        let contents = "[Script Info]\nScriptType: v4.00\n\n[Events]\nFormat: Marked, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\nDialogue: Marked=0,0:00:01.00,0:00:02.50,*Default,Berlín,0000,0000,0000,,Hola.\n";
+        let (sections, _) = parse(contents, ParseMode::Strict).unwrap();
+        assert_eq!(sections[0].to, Duration::from_millis(2500));
+        assert_eq!(sections[0].speaker, Some(String::from("Berlín")));
+        assert_eq!(sections[0].style, Some(String::from("*Default")));
+    }
+
+    #[test]
+    fn test_invalid_dialogue() {
+        let contents = "[Events]\nFormat: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\nDialogue: 0,0:00:01.00,0:00:02.00,Default,,0,0,0,,Hola.\nDialogue: 0,0:00:03.00\nDialogue: 0,0:00:03,0:00:04.00,Default,,0,0,0,,Adiós.\n";
+        assert!(matches!(
+            parse(contents, ParseMode::Strict),
+            Err(SubtitleError::InvalidDialogue {
+                block: 2,
+                line: 4,
+                ..
+            })
+        ));
+        let (sections, warnings) = parse(contents, ParseMode::Lenient).unwrap();
+        assert_eq!(sections.len(), 1);
+        assert_eq!(warnings.len(), 2);
+        assert!(matches!(
+            warnings[1],
+            SubtitleError::InvalidTimes {
+                block: 3,
+                line: 5,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_hours_overflow() {
+        assert_eq!(
+            get_time("100:00:00.50"),
+            Some(Duration::from_millis(360_000_500))
+        );
+        assert_eq!(get_time("99999999999999999999:00:00.00"), None);
+        assert_eq!(get_time("5124095576030432:00:00.00"), None);
+        let contents = "[Events]\nDialogue: 0,99999999999999999999:00:01.00,0:00:02.00,Default,,0,0,0,,Hola.\nDialogue: 0,0:00:03.00,0:00:04.00,Default,,0,0,0,,Adiós.\n";
+        let (sections, warnings) = parse(contents, ParseMode::Lenient).unwrap();
+        assert_eq!(sections.len(), 1);
+        assert_eq!(sections[0].id, 1);
+        assert!(matches!(
+            warnings[0],
+            SubtitleError::InvalidTimes { block: 1, .. }
+        ));
+    }
+}
@@ -0,0 +1,307 @@
+use super::colour::Colour;
+use super::{ass, srt, vtt, Subtitle, SubtitleSection};
+use std::fs;
+use std::io;
+use std::time::Duration;
+
+///How the lines of the secondary (native) language are set apart from the primary (target) language
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum SecondaryStyle {
+    Italic,
+    Colour(Colour),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BilingualOptions {
+    ///sections which are less than `tolerance` apart are still shown together
+    pub tolerance: Duration,
+    pub secondary_style: SecondaryStyle,
+}
+
+impl Default for BilingualOptions {
+    fn default() -> Self {
+        BilingualOptions::new()
+    }
+}
+
+impl BilingualOptions {
+    pub fn new() -> BilingualOptions {
+        BilingualOptions {
+            tolerance: Duration::from_millis(500),
+            secondary_style: SecondaryStyle::Italic,
+        }
+    }
+}
+
+///A cue of a bilingual track. Either text can be missing if the other track has no matching section.
+#[derive(PartialEq, Eq, Debug)]
+pub struct BilingualCue {
+    pub from: Duration,
+    pub to: Duration,
+    pub primary: Option<String>,
+    pub secondary: Option<String>,
+}
+
+///Subtitle track showing the target language on top and the native language below
+#[derive(Debug)]
+pub struct BilingualSubtitle {
+    pub name: String,
+    pub cues: Vec<BilingualCue>,
+    pub secondary_style: SecondaryStyle,
+}
+
+impl BilingualSubtitle {
+    ///Merges the two tracks. Every section of `secondary` is shown with the section of `primary` it overlaps most.
+    ///Sections without a partner within `options.tolerance` get a cue of their own.
+    pub fn merge(
+        primary: &Subtitle,
+        secondary: &Subtitle,
+        options: &BilingualOptions,
+    ) -> BilingualSubtitle {
+        let tolerance = options.tolerance.as_millis() as i128;
+        let mut partners: Vec<Vec<&SubtitleSection>> = vec![Vec::new(); primary.sections.len()];
+        let mut unmatched: Vec<&SubtitleSection> = Vec::new();
+        for s in secondary.sections.iter().filter(|s| !s.text.is_empty()) {
+            let best = primary
+                .sections
+                .iter()
+                .enumerate()
+                .map(|(i, p)| (i, overlap(p, s)))
+                .filter(|(_, overlap)| *overlap > -tolerance)
+                .max_by_key(|(_, overlap)| *overlap);
+            match best {
+                Some((i, _)) => partners[i].push(s),
+                None => unmatched.push(s),
+            }
+        }
+        let mut cues: Vec<BilingualCue> = primary
+            .sections
+            .iter()
+            .zip(partners.iter())
+            .map(|(p, partners)| BilingualCue {
+                from: p.from,
+                to: p.to,
+                primary: Some(p.text.clone()).filter(|text| !text.is_empty()),
+                secondary: if partners.is_empty() {
+                    None
+                } else {
+                    Some(
+                        partners
+                            .iter()
+                            .map(|s| s.text.as_str())
+                            .collect::<Vec<&str>>()
+                            .join(" "),
+                    )
+                },
+            })
+            .collect();
+        cues.extend(unmatched.iter().map(|s| BilingualCue {
+            from: s.from,
+            to: s.to,
+            primary: None,
+            secondary: Some(s.text.clone()),
+        }));
+        cues.retain(|c| c.primary.is_some() || c.secondary.is_some());
+        cues.sort_by_key(|c| c.from);
+        BilingualSubtitle {
+            name: format!("{} | {}", primary.name, secondary.name),
+            cues,
+            secondary_style: options.secondary_style,
+        }
+    }
+
+    pub fn to_srt_string(&self) -> String {
+        srt::write(&self.sections(
+            |text| String::from(text),
+            |text| match self.secondary_style {
+                SecondaryStyle::Italic => format!("<i>{}</i>", text),
+                SecondaryStyle::Colour(c) => {
+                    format!("<font color=\"{}\">{}</font>", c.to_hex(), text)
+                }
+            },
+        ))
+    }
+
+    pub fn to_vtt_string(&self) -> String {
+        let sections = self.sections(vtt::escape_cue_text, |text| match self.secondary_style {
+            SecondaryStyle::Italic => format!("<i>{}</i>", vtt::escape_cue_text(text)),
+            SecondaryStyle::Colour(_) => {
+                format!("<c.secondary>{}</c>", vtt::escape_cue_text(text))
+            }
+        });
+        let style = match self.secondary_style {
+            SecondaryStyle::Colour(c) => Some(format!(
+                "::cue(.secondary) {{\n  color: {};\n}}",
+                c.to_hex()
+            )),
+            SecondaryStyle::Italic => None,
+        };
+        //the texts are already escaped
+        vtt::write_with(&sections, style.as_deref(), |text| String::from(text))
+    }
+
+    ///The secondary text is switched to the style `Secondary` by the override block `{\rSecondary}`
+    pub fn to_ass_string(&self) -> String {
+        let white = Colour::new(255, 255, 255);
+        let styles = [
+            ass::Style {
+                name: String::from("Primary"),
+                colour: white,
+                italic: false,
+            },
+            ass::Style {
+                name: String::from("Secondary"),
+                colour: match self.secondary_style {
+                    SecondaryStyle::Italic => white,
+                    SecondaryStyle::Colour(c) => c,
+                },
+                italic: self.secondary_style == SecondaryStyle::Italic,
+            },
+        ];
+        let sections = self.sections(
+            |text| String::from(text),
+            |text| format!("{{\\rSecondary}}{}", text),
+        );
+        ass::write(&self.name, &styles, &sections)
+    }
+
+    pub fn write_srt(&self, filename: &str) -> io::Result<()> {
+        fs::write(filename, self.to_srt_string())
+    }
+
+    pub fn write_vtt(&self, filename: &str) -> io::Result<()> {
+        fs::write(filename, self.to_vtt_string())
+    }
+
+    pub fn write_ass(&self, filename: &str) -> io::Result<()> {
+        fs::write(filename, self.to_ass_string())
+    }
+
+    ///Puts the formatted texts of each cue on two lines
+    fn sections<P, S>(&self, primary: P, secondary: S) -> Vec<SubtitleSection>
+    where
+        P: Fn(&str) -> String,
+        S: Fn(&str) -> String,
+    {
+        self.cues
+            .iter()
+            .map(|cue| {
+                let mut lines = Vec::new();
+                if let Some(text) = &cue.primary {
+                    lines.push(primary(text));
+                }
+                if let Some(text) = &cue.secondary {
+                    lines.push(secondary(text));
+                }
+                let mut section = SubtitleSection::new();
+                section.from = cue.from;
+                section.to = cue.to;
+                section.time_index = cue.from.as_millis();
+                section.text = lines.join("\n");
+                section
+            })
+            .collect()
+    }
+}
+
+///Overlap of the two sections in milliseconds. Negative values are the gap between them.
+fn overlap(a: &SubtitleSection, b: &SubtitleSection) -> i128 {
+    let start = a.from.max(b.from).as_millis() as i128;
+    let end = a.to.min(b.to).as_millis() as i128;
+    end - start
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::subtitle::ParseMode;
+
+    fn subtitles() -> (Subtitle, Subtitle) {
+        let es = Subtitle::from_srt_string(
+            "es",
+            "1\n00:00:01,000 --> 00:00:03,000\n¿Vienes?\n\n2\n00:00:04,000 --> 00:00:06,000\nNo & punto.\n\n3\n00:00:20,000 --> 00:00:21,000\nAdiós.\n",
+            ParseMode::Strict,
+        )
+        .unwrap();
+        //the english track is 300ms later, splits the second section and has an extra section
+        let en = Subtitle::from_srt_string(
+            "en",
+            "1\n00:00:01,300 --> 00:00:03,300\nAre you coming?\n\n2\n00:00:04,300 --> 00:00:05,000\nNo.\n\n3\n00:00:05,100 --> 00:00:06,300\nPeriod.\n\n4\n00:00:10,000 --> 00:00:11,000\nHey!\n",
+            ParseMode::Strict,
+        )
+        .unwrap();
+        (es, en)
+    }
+
+    #[test]
+    fn test_merge() {
+        let (es, en) = subtitles();
+        let bilingual = BilingualSubtitle::merge(&es, &en, &BilingualOptions::new());
+        assert_eq!(bilingual.name, "es | en");
+        assert_eq!(bilingual.cues.len(), 4);
+        assert_eq!(
+            bilingual.cues[0],
+            BilingualCue {
+                from: Duration::from_millis(1000),
+                to: Duration::from_millis(3000),
+                primary: Some(String::from("¿Vienes?")),
+                secondary: Some(String::from("Are you coming?")),
+            }
+        );
+        assert_eq!(
+            bilingual.cues[1].secondary,
+            Some(String::from("No. Period."))
+        );
+        assert_eq!(bilingual.cues[2].primary, None);
+        assert_eq!(bilingual.cues[2].secondary, Some(String::from("Hey!")));
+        assert_eq!(bilingual.cues[3].secondary, None);
+    }
+
+    #[test]
+    fn test_tolerance() {
+        let (es, en) = subtitles();
+        let mut options = BilingualOptions::new();
+        options.tolerance = Duration::from_secs(10);
+        let bilingual = BilingualSubtitle::merge(&es, &en, &options);
+        assert_eq!(bilingual.cues.len(), 3);
+        assert_eq!(
+            bilingual.cues[1].secondary,
+            Some(String::from("No. Period. Hey!"))
+        );
+    }
+
+    #[test]
+    fn test_output_formats() {
+        let (es, en) = subtitles();
+        let mut options = BilingualOptions::new();
+        let bilingual = BilingualSubtitle::merge(&es, &en, &options);
+        assert!(bilingual
+            .to_srt_string()
+            .starts_with("1\n00:00:01,000 --> 00:00:03,000\n¿Vienes?\n<i>Are you coming?</i>\n\n"));
+        assert!(bilingual
+            .to_vtt_string()
+            .contains("\nNo &amp; punto.\n<i>No. Period.</i>\n"));
+        options.secondary_style = SecondaryStyle::Colour(Colour::new(255, 255, 0));
+        let bilingual = BilingualSubtitle::merge(&es, &en, &options);
+        assert!(bilingual
+            .to_srt_string()
+            .contains("\n¿Vienes?\n<font color=\"#ffff00\">Are you coming?</font>\n"));
+        let vtt = bilingual.to_vtt_string();
+        assert!(
+            vtt.starts_with("WEBVTT\n\nSTYLE\n::cue(.secondary) {\n  color: #ffff00;\n}\n\n1\n")
+        );
+        assert!(vtt.contains("\n<c.secondary>Hey!</c>\n"));
+        let ass = bilingual.to_ass_string();
+        assert!(ass.contains("Style: Secondary,Arial,16,&H0000FFFF,"));
+        assert!(ass.contains(
+            "Dialogue: 0,0:00:01.00,0:00:03.00,Primary,,0,0,0,,¿Vienes?\\N{\\rSecondary}Are you coming?\n"
+        ));
+        //all three formats can be read again
+        let srt = Subtitle::from_srt_string("srt", &bilingual.to_srt_string(), ParseMode::Strict);
+        assert_eq!(srt.unwrap().sections[0].text, "¿Vienes? Are you coming?");
+        let vtt = Subtitle::from_vtt_string("vtt", &vtt, ParseMode::Strict).unwrap();
+        assert_eq!(vtt.sections[1].text, "No & punto. No. Period.");
+        let ass = Subtitle::from_ass_string("ass", &ass, ParseMode::Strict).unwrap();
+        assert_eq!(ass.sections[3].text, "Adiós.");
+    }
+}
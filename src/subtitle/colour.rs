@@ -0,0 +1,99 @@
+use serde::{Deserialize, Serialize};
+
+///The colour classes WebVTT defines by default, which SRT files also use as font colours
+const NAMES: [(&str, Colour); 9] = [
+    ("white", Colour::new(255, 255, 255)),
+    ("lime", Colour::new(0, 255, 0)),
+    ("green", Colour::new(0, 128, 0)),
+    ("cyan", Colour::new(0, 255, 255)),
+    ("red", Colour::new(255, 0, 0)),
+    ("yellow", Colour::new(255, 255, 0)),
+    ("magenta", Colour::new(255, 0, 255)),
+    ("blue", Colour::new(0, 0, 255)),
+    ("black", Colour::new(0, 0, 0)),
+];
+
+///RGB colour of subtitle text
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy)]
+pub struct Colour {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+}
+
+impl Colour {
+    pub const fn new(red: u8, green: u8, blue: u8) -> Colour {
+        Colour { red, green, blue }
+    }
+
+    ///Parses `#rrggbb` or `rrggbb`
+    pub fn from_hex(hex: &str) -> Option<Colour> {
+        let hex = hex.trim_start_matches('#');
+        if hex.len() != 6 || !hex.is_ascii() {
+            return None;
+        }
+        let component = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        Some(Colour::new(component(0)?, component(2)?, component(4)?))
+    }
+
+    ///Parses a colour name like "yellow" or `#rrggbb`
+    pub fn from_name(name: &str) -> Option<Colour> {
+        NAMES
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name.trim()))
+            .map(|(_, colour)| *colour)
+            .or_else(|| Colour::from_hex(name.trim()))
+    }
+
+    ///The name of the colour if it is one of the WebVTT colour classes
+    pub fn name(&self) -> Option<&'static str> {
+        NAMES
+            .iter()
+            .find(|(_, colour)| colour == self)
+            .map(|(n, _)| *n)
+    }
+
+    ///Parses `&H00BBGGRR` of ASS styles or `&HBBGGRR&` of ASS override tags
+    pub fn from_ass(ass: &str) -> Option<Colour> {
+        let hex = ass.trim().trim_start_matches("&H").trim_end_matches('&');
+        if hex.len() < 6 || hex.len() > 8 || !hex.is_ascii() {
+            return None;
+        }
+        let hex = &hex[hex.len() - 6..];
+        let component = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        Some(Colour::new(component(4)?, component(2)?, component(0)?))
+    }
+
+    ///`#rrggbb` as used by SRT font tags and WebVTT style sheets
+    pub fn to_hex(&self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.red, self.green, self.blue)
+    }
+
+    ///`&H00BBGGRR` as used by ASS styles
+    pub fn to_ass(&self) -> String {
+        format!("&H00{:02X}{:02X}{:02X}", self.blue, self.green, self.red)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversions() {
+        let yellow = Colour::from_hex("#FFFF00").unwrap();
+        assert_eq!(yellow, Colour::new(255, 255, 0));
+        assert_eq!(yellow.to_hex(), "#ffff00");
+        assert_eq!(Colour::new(0x12, 0x34, 0x56).to_ass(), "&H00563412");
+        assert_eq!(Colour::from_hex("12345"), None);
+        assert_eq!(Colour::from_hex("gg0000"), None);
+        assert_eq!(Colour::from_name("Yellow"), Some(yellow));
+        assert_eq!(Colour::from_name("#ffff00"), Some(yellow));
+        assert_eq!(yellow.name(), Some("yellow"));
+        assert_eq!(Colour::from_ass("&H00FFFF&"), Some(yellow));
+        assert_eq!(
+            Colour::from_ass("&H00563412"),
+            Some(Colour::new(0x12, 0x34, 0x56))
+        );
+    }
+}
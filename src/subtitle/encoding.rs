@@ -0,0 +1,157 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+
+///Character encoding of a subtitle file before it was transcoded to UTF-8
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum Encoding {
+    #[default]
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    ///Windows-1252, a superset of ISO-8859-1 in the printable range
+    Windows1252,
+    ///ISO-8859-1 (Latin-1)
+    Latin1,
+}
+
+///Characters of Windows-1252 for the bytes 0x80 to 0x9F. The unused bytes keep their Latin-1 control character.
+const WINDOWS_1252: [char; 32] = [
+    '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8d}', 'Ž', '\u{8f}',
+    '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9d}', 'ž', 'Ÿ',
+];
+
+///Reads a file and transcodes it to UTF-8. See `decode`.
+pub fn read_to_string(filename: &str) -> io::Result<(String, Encoding)> {
+    let bytes = fs::read(filename)?;
+    Ok(decode(&bytes))
+}
+
+///Detects the encoding of the bytes and transcodes them to UTF-8. A byte order mark is removed.
+pub fn decode(bytes: &[u8]) -> (String, Encoding) {
+    let encoding = detect(bytes);
+    let text = match encoding {
+        Encoding::Utf8 => {
+            let bytes = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(bytes);
+            //detect only returns Utf8 for valid UTF-8
+            String::from(std::str::from_utf8(bytes).unwrap())
+        }
+        Encoding::Utf16Le => decode_utf16(bytes, u16::from_le_bytes),
+        Encoding::Utf16Be => decode_utf16(bytes, u16::from_be_bytes),
+        Encoding::Windows1252 => bytes
+            .iter()
+            .map(|&b| match b {
+                0x80..=0x9F => WINDOWS_1252[(b - 0x80) as usize],
+                _ => b as char,
+            })
+            .collect(),
+        Encoding::Latin1 => bytes.iter().map(|&b| b as char).collect(),
+    };
+    (text, encoding)
+}
+
+///Detects the encoding by the byte order mark, the distribution of zero bytes for UTF-16 and
+///falls back to an 8 bit codepage if the bytes aren't valid UTF-8
+pub fn detect(bytes: &[u8]) -> Encoding {
+    if bytes.starts_with(&[0xEF, 0xBB, 0xBF]) && std::str::from_utf8(&bytes[3..]).is_ok() {
+        return Encoding::Utf8;
+    }
+    if bytes.starts_with(&[0xFF, 0xFE]) {
+        return Encoding::Utf16Le;
+    }
+    if bytes.starts_with(&[0xFE, 0xFF]) {
+        return Encoding::Utf16Be;
+    }
+    //subtitles are mostly ASCII, so UTF-16 without BOM has a zero byte in every second position
+    let sample = &bytes[..bytes.len().min(1000)];
+    let zeros_at = |offset: usize| {
+        sample
+            .iter()
+            .skip(offset)
+            .step_by(2)
+            .filter(|&&b| b == 0)
+            .count()
+    };
+    if sample.len() >= 4 {
+        let half = sample.len() / 2;
+        if zeros_at(1) * 10 > half * 4 && zeros_at(0) * 10 < half {
+            return Encoding::Utf16Le;
+        }
+        if zeros_at(0) * 10 > half * 4 && zeros_at(1) * 10 < half {
+            return Encoding::Utf16Be;
+        }
+    }
+    if std::str::from_utf8(bytes).is_ok() {
+        return Encoding::Utf8;
+    }
+    //bytes 0x80 to 0x9F are control characters in Latin-1 which don't appear in text,
+    //but quotes, dashes and ellipses in Windows-1252
+    if bytes.iter().any(|&b| (0x80..=0x9F).contains(&b)) {
+        Encoding::Windows1252
+    } else {
+        Encoding::Latin1
+    }
+}
+
+fn decode_utf16(bytes: &[u8], to_u16: fn([u8; 2]) -> u16) -> String {
+    let units = bytes.chunks_exact(2).map(|pair| to_u16([pair[0], pair[1]]));
+    let text: String = std::char::decode_utf16(units)
+        .map(|c| c.unwrap_or(std::char::REPLACEMENT_CHARACTER))
+        .collect();
+    match text.strip_prefix('\u{feff}') {
+        Some(text) => String::from(text),
+        None => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_utf8_bom() {
+        let (text, encoding) = decode("\u{feff}1\r\n¿Qué?".as_bytes());
+        assert_eq!(encoding, Encoding::Utf8);
+        assert_eq!(text, "1\r\n¿Qué?");
+    }
+
+    #[test]
+    fn test_utf16() {
+        let mut le = vec![0xFF, 0xFE];
+        let mut be = vec![0xFE, 0xFF];
+        let mut le_without_bom = vec![];
+        for unit in "1\r\n¿Señor?".encode_utf16() {
+            le.extend_from_slice(&unit.to_le_bytes());
+            be.extend_from_slice(&unit.to_be_bytes());
+            le_without_bom.extend_from_slice(&unit.to_le_bytes());
+        }
+        assert_eq!(
+            decode(&le),
+            (String::from("1\r\n¿Señor?"), Encoding::Utf16Le)
+        );
+        assert_eq!(
+            decode(&be),
+            (String::from("1\r\n¿Señor?"), Encoding::Utf16Be)
+        );
+        assert_eq!(
+            decode(&le_without_bom),
+            (String::from("1\r\n¿Señor?"), Encoding::Utf16Le)
+        );
+    }
+
+    #[test]
+    fn test_8_bit_codepages() {
+        //"¿Mañana?" and "Grüße" in Latin-1
+        let latin1 = b"\xbfMa\xf1ana? Gr\xfc\xdfe";
+        assert_eq!(
+            decode(latin1),
+            (String::from("¿Mañana? Grüße"), Encoding::Latin1)
+        );
+        //“Sí…” in Windows-1252
+        let windows = b"\x93S\xed\x85\x94";
+        assert_eq!(
+            decode(windows),
+            (String::from("“Sí…”"), Encoding::Windows1252)
+        );
+    }
+}
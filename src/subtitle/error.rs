@@ -0,0 +1,100 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+///Errors which can occur while reading and parsing a subtitle file.
+///`block` is the number of the subtitle block in the file and `line` the line in the file, both starting at 1.
+#[derive(Debug)]
+pub enum SubtitleError {
+    ///The file could not be read
+    Io(io::Error),
+    ///The file does not contain a single subtitle block
+    Empty,
+    ///The format could neither be detected from the content nor from the file extension
+    UnknownFormat { path: String },
+    ///The first line is not the header required by the format, e.g. `WEBVTT`
+    InvalidHeader { text: String },
+    ///The first line of a block is not a numeric id
+    InvalidId {
+        block: usize,
+        line: usize,
+        text: String,
+    },
+    ///The block ends before the time line
+    MissingTimes {
+        block: usize,
+        line: usize,
+        text: String,
+    },
+    ///The time line does not have the form `00:00:00,000 --> 00:00:00,000`
+    InvalidTimes {
+        block: usize,
+        line: usize,
+        text: String,
+    },
+    ///An event line doesn't contain the columns given by its format line
+    InvalidDialogue {
+        block: usize,
+        line: usize,
+        text: String,
+    },
+    ///The block has an id and a time line but no text. The block is skipped, only lenient parsing reports it as a warning.
+    MissingText {
+        block: usize,
+        line: usize,
+        text: String,
+    },
+}
+
+impl fmt::Display for SubtitleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SubtitleError::Io(e) => write!(f, "could not read subtitle file: {}", e),
+            SubtitleError::Empty => write!(f, "subtitle file does not contain any block"),
+            SubtitleError::UnknownFormat { path } => {
+                write!(f, "unknown subtitle format of \"{}\"", path)
+            }
+            SubtitleError::InvalidHeader { text } => write!(f, "invalid header \"{}\"", text),
+            SubtitleError::InvalidId { block, line, text } => write!(
+                f,
+                "block {} (line {}): invalid id \"{}\"",
+                block, line, text
+            ),
+            SubtitleError::MissingTimes { block, line, text } => write!(
+                f,
+                "block {} (line {}): missing time line after \"{}\"",
+                block, line, text
+            ),
+            SubtitleError::InvalidTimes { block, line, text } => write!(
+                f,
+                "block {} (line {}): invalid time line \"{}\"",
+                block, line, text
+            ),
+            SubtitleError::InvalidDialogue { block, line, text } => write!(
+                f,
+                "block {} (line {}): invalid dialogue \"{}\"",
+                block, line, text
+            ),
+            SubtitleError::MissingText { block, line, text } => write!(
+                f,
+                "block {} (line {}): missing text after \"{}\"",
+                block, line, text
+            ),
+        }
+    }
+}
+
+impl Error for SubtitleError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SubtitleError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SubtitleError {
+    fn from(e: io::Error) -> SubtitleError {
+        SubtitleError::Io(e)
+    }
+}
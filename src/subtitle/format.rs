@@ -0,0 +1,106 @@
+use super::vtt;
+use crate::toolbox;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+///Subtitle file formats which can be parsed
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum SubtitleFormat {
+    ///SubRip (.srt)
+    #[default]
+    Srt,
+    ///WebVTT (.vtt)
+    WebVtt,
+    ///Advanced SubStation Alpha (.ass) and SubStation Alpha (.ssa)
+    Ass,
+}
+
+impl SubtitleFormat {
+    ///Detects the format from the beginning of the content
+    pub fn detect(contents: &str) -> Option<SubtitleFormat> {
+        let mut lines = toolbox::split_lines(contents.trim_start_matches('\u{feff}'))
+            .into_iter()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty());
+        let first = lines.next()?;
+        if vtt::is_header(first) {
+            return Some(SubtitleFormat::WebVtt);
+        }
+        if first.eq_ignore_ascii_case("[Script Info]") {
+            return Some(SubtitleFormat::Ass);
+        }
+        //SRT starts with the number of the first block followed by its time line
+        if first.parse::<u64>().is_ok() {
+            if let Some(second) = lines.next() {
+                if second.contains("-->") {
+                    return Some(SubtitleFormat::Srt);
+                }
+            }
+        }
+        None
+    }
+
+    ///Guesses the format from the file extension
+    pub fn from_extension(path: &str) -> Option<SubtitleFormat> {
+        let extension = Path::new(path).extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "srt" => Some(SubtitleFormat::Srt),
+            "vtt" => Some(SubtitleFormat::WebVtt),
+            "ass" | "ssa" => Some(SubtitleFormat::Ass),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        let srt = std::fs::read_to_string("./resources/Subtitles/papel_S01E01_en.srt").unwrap();
+        assert!(srt.starts_with('\u{feff}'));
+        assert_eq!(SubtitleFormat::detect(&srt), Some(SubtitleFormat::Srt));
+        let vtt = std::fs::read_to_string("./resources/test/test.vtt").unwrap();
+        assert_eq!(SubtitleFormat::detect(&vtt), Some(SubtitleFormat::WebVtt));
+        let ass = std::fs::read_to_string("./resources/test/test.ass").unwrap();
+        assert_eq!(SubtitleFormat::detect(&ass), Some(SubtitleFormat::Ass));
+        assert_eq!(
+            SubtitleFormat::detect("\u{feff}\r\nWEBVTT\r\n"),
+            Some(SubtitleFormat::WebVtt)
+        );
+        assert_eq!(
+            SubtitleFormat::detect("WEBVTT - Episode 1\n"),
+            Some(SubtitleFormat::WebVtt)
+        );
+        assert_eq!(SubtitleFormat::detect("WEBVTTX\n"), None);
+        assert_eq!(
+            SubtitleFormat::detect("1\r00:00:01,000 --> 00:00:02,000\rHola\r"),
+            Some(SubtitleFormat::Srt)
+        );
+        assert_eq!(
+            SubtitleFormat::detect("\rWEBVTT\r\r00:01.000 --> 00:02.000\rHola\r"),
+            Some(SubtitleFormat::WebVtt)
+        );
+        assert_eq!(SubtitleFormat::detect("1\nHola"), None);
+        assert_eq!(SubtitleFormat::detect(""), None);
+    }
+
+    #[test]
+    fn test_from_extension() {
+        assert_eq!(
+            SubtitleFormat::from_extension("a/b.SRT"),
+            Some(SubtitleFormat::Srt)
+        );
+        assert_eq!(
+            SubtitleFormat::from_extension("b.vtt"),
+            Some(SubtitleFormat::WebVtt)
+        );
+        assert_eq!(
+            SubtitleFormat::from_extension("b.ssa"),
+            Some(SubtitleFormat::Ass)
+        );
+        assert_eq!(SubtitleFormat::from_extension("b.txt"), None);
+        assert_eq!(SubtitleFormat::from_extension("srt"), None);
+    }
+}
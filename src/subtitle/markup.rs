@@ -0,0 +1,358 @@
+use super::colour::Colour;
+use super::SubtitleSection;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+lazy_static! {
+    //html like tags of SRT and WebVTT, WebVTT timestamps and ASS override blocks, which SRT files use as well
+    static ref TOKEN_REGEX: Regex = Regex::new(
+        r#"<(/?)([a-zA-Z]+)([^>]*)>|<(?:\d{2,}:)?\d{2}:\d{2}\.\d{3}>|\{([^}]*)\}"#
+    )
+    .unwrap();
+    static ref FONT_COLOUR_REGEX: Regex = Regex::new(r#"(?i)color\s*=\s*["']?([#\w]+)"#).unwrap();
+    static ref OVERRIDE_REGEX: Regex = Regex::new(r#"\\(an|pos|[1]?c|i|b|p|r)([^\\]*)"#).unwrap();
+    static ref POS_REGEX: Regex = Regex::new(r#"\(\s*([-\d.]+)\s*,\s*([-\d.]+)\s*\)"#).unwrap();
+}
+
+///Formatting of a span of subtitle text
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SpanStyle {
+    #[serde(default)]
+    pub italic: bool,
+    #[serde(default)]
+    pub bold: bool,
+    ///font colour, which often marks the speaker
+    #[serde(default)]
+    pub colour: Option<Colour>,
+}
+
+///Text with the same formatting
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Span {
+    pub text: String,
+    #[serde(default)]
+    pub style: SpanStyle,
+}
+
+///Position of a cue on the screen
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum Position {
+    ///alignment like the numeric keypad as in ASS `{\an8}`: 1-3 bottom, 4-6 middle, 7-9 top
+    Alignment(u8),
+    ///point in script pixels as in ASS `{\pos(320,50)}`
+    Point(f64, f64),
+}
+
+///Parses the markup of the text lines of a cue into spans. The lines are separated by "\n" within the spans.
+///Unknown tags are removed, and so are ASS drawings.
+pub fn parse(lines: &[String]) -> (Vec<Span>, Option<Position>) {
+    let mut spans: Vec<Span> = Vec::new();
+    let mut position = None;
+    let mut style = SpanStyle::default();
+    let mut colours: Vec<Option<Colour>> = Vec::new();
+    let mut drawing = false;
+    for (i, line) in lines.iter().enumerate() {
+        if i > 0 {
+            push(&mut spans, "\n", style);
+        }
+        let mut start = 0;
+        for caps in TOKEN_REGEX.captures_iter(line) {
+            let token = caps.get(0).unwrap();
+            if !drawing {
+                push(&mut spans, &line[start..token.start()], style);
+            }
+            start = token.end();
+            if let Some(overrides) = caps.get(4) {
+                for o in OVERRIDE_REGEX.captures_iter(overrides.as_str()) {
+                    let value = o[2].trim();
+                    //\i1 but not \iclip, \b700 but not \bord2
+                    let flag = value.parse::<u32>().ok().map(|v| v != 0);
+                    match (&o[1], flag) {
+                        ("an", _) => position = value.parse().ok().map(Position::Alignment),
+                        ("pos", _) => {
+                            position = POS_REGEX.captures(value).and_then(|p| {
+                                Some(Position::Point(p[1].parse().ok()?, p[2].parse().ok()?))
+                            })
+                        }
+                        ("i", Some(on)) => style.italic = on,
+                        ("b", Some(on)) => style.bold = on,
+                        ("p", Some(on)) => drawing = on,
+                        ("c", _) | ("1c", _) if value.is_empty() || value.starts_with("&H") => {
+                            style.colour = Colour::from_ass(value)
+                        }
+                        ("r", _) => style = SpanStyle::default(),
+                        _ => (),
+                    }
+                }
+                continue;
+            }
+            let closing = caps.get(1).is_some_and(|c| !c.as_str().is_empty());
+            let name = caps.get(2).map_or("", |n| n.as_str()).to_lowercase();
+            let attributes = caps.get(3).map_or("", |a| a.as_str());
+            match (name.as_str(), closing) {
+                ("i", _) => style.italic = !closing,
+                ("b", _) => style.bold = !closing,
+                ("font", false) => {
+                    colours.push(style.colour);
+                    if let Some(colour) = FONT_COLOUR_REGEX.captures(attributes) {
+                        style.colour = Colour::from_name(&colour[1]);
+                    }
+                }
+                ("c", false) => {
+                    colours.push(style.colour);
+                    if let Some(colour) = attributes.split('.').find_map(Colour::from_name) {
+                        style.colour = Some(colour);
+                    }
+                }
+                ("font", true) | ("c", true) => style.colour = colours.pop().flatten(),
+                _ => (),
+            }
+        }
+        if !drawing {
+            push(&mut spans, &line[start..], style);
+        }
+    }
+    (spans, position)
+}
+
+///Appends the text to the last span if it has the same style
+fn push(spans: &mut Vec<Span>, text: &str, style: SpanStyle) {
+    let text = text.replace("\\h", " ");
+    if text.is_empty() {
+        return;
+    }
+    match spans.last_mut() {
+        Some(last) if last.style == style => last.text.push_str(&text),
+        _ => spans.push(Span { text, style }),
+    }
+}
+
+///Writes the spans with SRT tags
+pub fn to_srt(spans: &[Span], position: Option<Position>) -> String {
+    let mut result = match position {
+        Some(Position::Alignment(alignment)) if alignment != 2 => format!("{{\\an{}}}", alignment),
+        _ => String::new(),
+    };
+    for span in spans {
+        let mut open = String::new();
+        let mut close = String::new();
+        if let Some(colour) = span.style.colour {
+            open.push_str(&format!("<font color=\"{}\">", colour.to_hex()));
+            close.insert_str(0, "</font>");
+        }
+        if span.style.bold {
+            open.push_str("<b>");
+            close.insert_str(0, "</b>");
+        }
+        if span.style.italic {
+            open.push_str("<i>");
+            close.insert_str(0, "</i>");
+        }
+        //the tags are closed at the end of each line like in most SRT files
+        let lines: Vec<String> = span
+            .text
+            .split('\n')
+            .map(|line| {
+                if line.is_empty() {
+                    String::new()
+                } else {
+                    format!("{}{}{}", open, line, close)
+                }
+            })
+            .collect();
+        result.push_str(&lines.join("\n"));
+    }
+    result
+}
+
+///Writes the spans with WebVTT tags. Only the colours of the WebVTT colour classes are kept.
+pub fn to_vtt(spans: &[Span], escape: fn(&str) -> String) -> String {
+    let mut result = String::new();
+    for span in spans {
+        let mut open = String::new();
+        let mut close = String::new();
+        if let Some(name) = span.style.colour.and_then(|c| c.name()) {
+            open.push_str(&format!("<c.{}>", name));
+            close.insert_str(0, "</c>");
+        }
+        if span.style.bold {
+            open.push_str("<b>");
+            close.insert_str(0, "</b>");
+        }
+        if span.style.italic {
+            open.push_str("<i>");
+            close.insert_str(0, "</i>");
+        }
+        result.push_str(&format!("{}{}{}", open, escape(&span.text), close));
+    }
+    result
+}
+
+///Writes the spans with ASS override tags
+pub fn to_ass(spans: &[Span], position: Option<Position>) -> String {
+    let mut result = match position {
+        Some(Position::Alignment(alignment)) if alignment != 2 => format!("{{\\an{}}}", alignment),
+        Some(Position::Point(x, y)) => format!("{{\\pos({},{})}}", x, y),
+        _ => String::new(),
+    };
+    for span in spans {
+        let mut overrides = String::new();
+        if span.style.italic {
+            overrides.push_str("\\i1");
+        }
+        if span.style.bold {
+            overrides.push_str("\\b1");
+        }
+        if let Some(c) = span.style.colour {
+            overrides.push_str(&format!("\\c&H{:02X}{:02X}{:02X}&", c.blue, c.green, c.red));
+        }
+        let text = span.text.replace('\n', "\\N");
+        if overrides.is_empty() {
+            result.push_str(&text);
+        } else {
+            result.push_str(&format!("{{{}}}{}{{\\r}}", overrides, text));
+        }
+    }
+    result
+}
+
+///Marks the sections spoken by a narrator or an off-screen voice. These are written in italics,
+///or, as in Spanish subtitles, start with an opening quotation mark which is closed in a later section
+///of the same colour.
+pub fn mark_narration(sections: &mut [SubtitleSection]) {
+    //colour of the voice whose quotation is still open
+    let mut open_quote: Option<Option<Colour>> = None;
+    for section in sections.iter_mut() {
+        let letters: Vec<&Span> = section
+            .spans
+            .iter()
+            .filter(|s| s.text.chars().any(char::is_alphabetic))
+            .collect();
+        if letters.is_empty() {
+            continue;
+        }
+        let colour = letters[0].style.colour;
+        let text: String = letters.iter().map(|s| s.text.as_str()).collect();
+        let text = text.trim_start_matches(|c: char| c == '-' || c.is_whitespace());
+        if open_quote.is_some_and(|c| c != colour) {
+            open_quote = None;
+        }
+        if open_quote.is_none() && starts_quote(text) {
+            open_quote = Some(colour);
+        }
+        section.narration = open_quote.is_some() || letters.iter().all(|s| s.style.italic);
+        if open_quote.is_some() && closes_quote(text) {
+            open_quote = None;
+        }
+    }
+}
+
+fn starts_quote(text: &str) -> bool {
+    text.starts_with(['"', '«', '“'])
+}
+
+///A closing quotation mark after the first character
+fn closes_quote(text: &str) -> bool {
+    text.char_indices().skip(1).any(|(i, c)| match c {
+        '»' | '”' => true,
+        //a straight quote closes if it doesn't start a word
+        '"' => !text[i + 1..].starts_with(char::is_alphanumeric),
+        _ => false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| String::from(*line)).collect()
+    }
+
+    fn span(text: &str, italic: bool, bold: bool, colour: Option<Colour>) -> Span {
+        Span {
+            text: String::from(text),
+            style: SpanStyle {
+                italic,
+                bold,
+                colour,
+            },
+        }
+    }
+
+    #[test]
+    fn test_parse_srt() {
+        let yellow = Colour::from_name("yellow");
+        let (spans, position) = parse(&lines(&[
+            "{\\an8}<font color=\"yellow\">Me llamo <b>Tokio</b>.</font>",
+            "<i>Hola</i>",
+        ]));
+        assert_eq!(position, Some(Position::Alignment(8)));
+        assert_eq!(
+            spans,
+            vec![
+                span("Me llamo ", false, false, yellow),
+                span("Tokio", false, true, yellow),
+                span(".", false, false, yellow),
+                span("\n", false, false, None),
+                span("Hola", true, false, None),
+            ]
+        );
+        assert_eq!(
+            to_srt(&spans, position),
+            "{\\an8}<font color=\"#ffff00\">Me llamo </font><font color=\"#ffff00\"><b>Tokio</b></font><font color=\"#ffff00\">.</font>\n<i>Hola</i>"
+        );
+    }
+
+    #[test]
+    fn test_parse_vtt_and_ass() {
+        let (spans, _) = parse(&lines(&["<v Tokio><c.yellow.bg_black>Tú</c> y <i>yo</i>"]));
+        assert_eq!(
+            spans[0],
+            span("Tú", false, false, Colour::from_name("yellow"))
+        );
+        assert_eq!(spans[1], span(" y ", false, false, None));
+        assert_eq!(
+            to_vtt(&spans, |text| String::from(text)),
+            "<c.yellow>Tú</c> y <i>yo</i>"
+        );
+        let (spans, position) = parse(&lines(&[
+            "{\\pos(320,50)\\i1}Banco{\\i0\\c&H0000FF&} de{\\r} España{\\p1}m 0 0 l 100 0{\\p0}",
+        ]));
+        assert_eq!(position, Some(Position::Point(320.0, 50.0)));
+        assert_eq!(
+            spans,
+            vec![
+                span("Banco", true, false, None),
+                span(" de", false, false, Colour::from_name("red")),
+                span(" España", false, false, None),
+            ]
+        );
+        assert_eq!(
+            to_ass(&spans, position),
+            "{\\pos(320,50)}{\\i1}Banco{\\r}{\\c&H0000FF&} de{\\r} España"
+        );
+    }
+
+    #[test]
+    fn test_narration() {
+        let mut sections: Vec<SubtitleSection> = vec![
+            vec!["<font color=\"yellow\">\"Me llamo Tokio.</font>"],
+            vec!["<font color=\"yellow\">Esta era yo.\".</font>"],
+            vec!["<font color=\"yellow\">¡Corre!</font>"],
+            vec!["<i>Te estoy viendo.</i>"],
+            vec!["<font color=\"cyan\">\"¿Mamá?</font>"],
+            vec!["<font color=\"yellow\">¿Sí?</font>"],
+        ]
+        .into_iter()
+        .map(|l| {
+            let mut section = SubtitleSection::new();
+            section.spans = parse(&lines(&l)).0;
+            section
+        })
+        .collect();
+        mark_narration(&mut sections);
+        let narration: Vec<bool> = sections.iter().map(|s| s.narration).collect();
+        assert_eq!(narration, vec![true, true, false, true, true, false]);
+    }
+}
@@ -0,0 +1,302 @@
+use super::SentenceExtractionResult;
+use crate::flashcard::Language;
+
+///Splits the text of a section into sentences and the parts of sentences spanning several sections
+pub trait SentenceSegmenter {
+    ///`continues` is true if the previous section ended inside a sentence
+    fn segment(&self, text: &str, continues: bool) -> SentenceExtractionResult;
+}
+
+const TERMINATORS: [char; 4] = ['.', '!', '?', '…'];
+const DASHES: [char; 3] = ['-', '–', '—'];
+
+///Rule based segmentation. A sentence ends with `.`, `!`, `?` or an ellipsis
+///if the next word starts with an uppercase letter, a digit or an opener.
+///An ellipsis at the end of the text leaves the sentence open, as it often continues in the next section.
+pub struct RuleSegmenter {
+    ///abbreviations including their dots like "Sr." or "z.B.", which don't end a sentence. Compared ignoring case.
+    pub abbreviations: Vec<String>,
+    ///characters which can precede the first word of a sentence, e.g. "¿", quotes and dialogue dashes
+    pub openers: Vec<char>,
+    ///characters which can follow the terminator and still belong to the sentence, e.g. closing quotes
+    pub closers: Vec<char>,
+    ///true for languages like German, where an uppercase word after an unfinished sentence doesn't start a new one
+    pub capitalised_nouns: bool,
+}
+
+impl RuleSegmenter {
+    ///Rules without abbreviations which work for most languages written in latin script
+    pub fn new() -> RuleSegmenter {
+        RuleSegmenter {
+            abbreviations: vec![],
+            openers: vec!['¿', '¡', '"', '«', '“', '„', '-', '–', '—'],
+            closers: vec!['"', '»', '”', '“', '’', ')'],
+            capitalised_nouns: false,
+        }
+    }
+
+    pub fn spanish() -> RuleSegmenter {
+        RuleSegmenter {
+            abbreviations: to_strings(&[
+                "Sr.", "Sra.", "Srta.", "Sres.", "Dr.", "Dra.", "Dña.", "Ud.", "Uds.", "Vd.",
+                "Vds.", "Lic.", "Ing.", "etc.", "aprox.", "pág.", "núm.", "tel.", "p.ej.",
+                "EE.UU.", "a.m.", "p.m.",
+            ]),
+            ..RuleSegmenter::new()
+        }
+    }
+
+    pub fn english() -> RuleSegmenter {
+        RuleSegmenter {
+            abbreviations: to_strings(&[
+                "Mr.", "Mrs.", "Ms.", "Dr.", "Prof.", "St.", "Jr.", "Sr.", "Lt.", "Sgt.", "Capt.",
+                "Col.", "Gen.", "vs.", "etc.", "e.g.", "i.e.", "a.m.", "p.m.", "U.S.",
+            ]),
+            ..RuleSegmenter::new()
+        }
+    }
+
+    pub fn german() -> RuleSegmenter {
+        RuleSegmenter {
+            abbreviations: to_strings(&[
+                "z.B.", "d.h.", "u.a.", "usw.", "bzw.", "ca.", "evtl.", "ggf.", "Hr.", "Hrn.",
+                "Fr.", "Dr.", "Prof.", "Nr.", "Str.", "vgl.", "inkl.", "bspw.",
+            ]),
+            capitalised_nouns: true,
+            ..RuleSegmenter::new()
+        }
+    }
+
+    pub fn for_language(language: &Language) -> RuleSegmenter {
+        match language {
+            Language::English => RuleSegmenter::english(),
+            Language::German => RuleSegmenter::german(),
+            Language::Spanish => RuleSegmenter::spanish(),
+        }
+    }
+
+    ///Splits the text at the end of each sentence. The flag tells if the piece ends with a terminator,
+    ///which is only false for the last piece.
+    pub fn split<'a>(&self, text: &'a str) -> Vec<(&'a str, bool)> {
+        let chars: Vec<(usize, char)> = text.char_indices().collect();
+        let mut pieces = Vec::new();
+        let mut start = 0;
+        let mut i = 0;
+        while i < chars.len() {
+            if !TERMINATORS.contains(&chars[i].1) {
+                i += 1;
+                continue;
+            }
+            let first = chars[i].0;
+            while i < chars.len() && TERMINATORS.contains(&chars[i].1) {
+                i += 1;
+            }
+            let terminators_end = chars.get(i).map_or(text.len(), |(index, _)| *index);
+            while i < chars.len() && self.closers.contains(&chars[i].1) {
+                i += 1;
+            }
+            let end = chars.get(i).map_or(text.len(), |(index, _)| *index);
+            if self.is_boundary(
+                &text[start..first],
+                &text[first..terminators_end],
+                &text[end..],
+            ) {
+                pieces.push((text[start..end].trim(), true));
+                start = end;
+            }
+        }
+        let rest = text[start..].trim();
+        if !rest.is_empty() {
+            pieces.push((rest, false));
+        }
+        pieces
+    }
+
+    fn is_boundary(&self, before: &str, terminators: &str, after: &str) -> bool {
+        if !before.chars().any(char::is_alphanumeric) {
+            return false;
+        }
+        match after.chars().next() {
+            //an ellipsis at the end of the text suspends the sentence, which may continue in the next section
+            None => return !is_ellipsis(terminators),
+            //3.5, z.B. or EE.UU.
+            Some(c) if c.is_alphanumeric() => return false,
+            _ => (),
+        }
+        if terminators == "." && self.is_abbreviation(before) {
+            return false;
+        }
+        match after.trim_start().chars().next() {
+            None => true,
+            Some(c) => c.is_uppercase() || c.is_numeric() || self.openers.contains(&c),
+        }
+    }
+
+    fn is_abbreviation(&self, before: &str) -> bool {
+        let word = before
+            .rsplit(char::is_whitespace)
+            .next()
+            .unwrap_or("")
+            .trim_start_matches(|c| self.openers.contains(&c));
+        let word = format!("{}.", word).to_lowercase();
+        self.abbreviations.iter().any(|a| a.to_lowercase() == word)
+    }
+
+    ///True if the piece belongs to the sentence of the previous section
+    fn continues_sentence(&self, piece: &str, continues: bool) -> bool {
+        let first = piece
+            .chars()
+            .find(|c| !c.is_whitespace() && !self.openers.contains(c) && !TERMINATORS.contains(c));
+        let starts_sentence = first.is_some_and(|c| c.is_uppercase() || c.is_numeric());
+        let marked = piece.starts_with(|c| self.openers.contains(&c));
+        !starts_sentence || (continues && self.capitalised_nouns && !marked)
+    }
+}
+
+impl Default for RuleSegmenter {
+    fn default() -> Self {
+        RuleSegmenter::new()
+    }
+}
+
+impl SentenceSegmenter for RuleSegmenter {
+    fn segment(&self, text: &str, continues: bool) -> SentenceExtractionResult {
+        let mut result = SentenceExtractionResult {
+            end_of_a_sentence: None,
+            sentences: None,
+            begin_of_a_sentence: None,
+            partial: None,
+        };
+        let pieces = self.split(text);
+        if let [(piece, false)] = pieces.as_slice() {
+            result.partial = Some(strip_dialogue_dash(piece));
+            return result;
+        }
+        let mut sentences = Vec::new();
+        for (i, (piece, terminated)) in pieces.into_iter().enumerate() {
+            if !terminated {
+                result.begin_of_a_sentence = Some(strip_dialogue_dash(piece));
+            } else if i == 0 && self.continues_sentence(piece, continues) {
+                result.end_of_a_sentence = Some(strip_dialogue_dash(piece));
+            } else {
+                sentences.push(strip_dialogue_dash(piece));
+            }
+        }
+        if !sentences.is_empty() {
+            result.sentences = Some(sentences);
+        }
+        result
+    }
+}
+
+fn is_ellipsis(terminators: &str) -> bool {
+    terminators.ends_with("..") || terminators.ends_with('…')
+}
+
+///Removes the dash which marks a new speaker in front of a sentence
+fn strip_dialogue_dash(piece: &str) -> String {
+    String::from(piece.trim_start_matches(|c| DASHES.contains(&c)).trim())
+}
+
+fn to_strings(words: &[&str]) -> Vec<String> {
+    words.iter().map(|word| String::from(*word)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sentences(segmenter: &RuleSegmenter, text: &str) -> Vec<String> {
+        segmenter.segment(text, false).sentences.unwrap_or_default()
+    }
+
+    #[test]
+    fn test_unicode_uppercase() {
+        let segmenter = RuleSegmenter::spanish();
+        assert_eq!(
+            sentences(&segmenter, "Ésta es mía. Ñoño, ven. Él no."),
+            vec!["Ésta es mía.", "Ñoño, ven.", "Él no."]
+        );
+    }
+
+    #[test]
+    fn test_abbreviations() {
+        let spanish = RuleSegmenter::spanish();
+        assert_eq!(
+            sentences(&spanish, "Buenos días, Sr. García. ¿Qué tal?"),
+            vec!["Buenos días, Sr. García.", "¿Qué tal?"]
+        );
+        let german = RuleSegmenter::german();
+        assert_eq!(
+            sentences(&german, "Wir brauchen z.B. Brot. Und Milch."),
+            vec!["Wir brauchen z.B. Brot.", "Und Milch."]
+        );
+        assert_eq!(
+            sentences(&RuleSegmenter::new(), "Sr. García."),
+            vec!["Sr.", "García."]
+        );
+    }
+
+    #[test]
+    fn test_ellipsis_and_numbers() {
+        let segmenter = RuleSegmenter::spanish();
+        assert_eq!(
+            sentences(&segmenter, "Pues... no sé. Vale... Cuesta 3.50 euros."),
+            vec!["Pues... no sé.", "Vale...", "Cuesta 3.50 euros."]
+        );
+    }
+
+    #[test]
+    fn test_dialogue() {
+        let segmenter = RuleSegmenter::spanish();
+        assert_eq!(
+            sentences(&segmenter, "- ¿Vienes? - No."),
+            vec!["¿Vienes?", "No."]
+        );
+        assert_eq!(
+            sentences(&segmenter, "\"Ven.\" Y se fue."),
+            vec!["\"Ven.\"", "Y se fue."]
+        );
+    }
+
+    #[test]
+    fn test_suspended_sentence() {
+        let segmenter = RuleSegmenter::spanish();
+        let result = segmenter.segment("Me gusta hacer…", false);
+        assert_eq!(result.partial, Some(String::from("Me gusta hacer…")));
+        let result = segmenter.segment("No. Me gusta hacer...", false);
+        assert_eq!(result.sentences, Some(vec![String::from("No.")]));
+        assert_eq!(
+            result.begin_of_a_sentence,
+            Some(String::from("Me gusta hacer..."))
+        );
+        let result = segmenter.segment("deporte en el banco móvil.", true);
+        assert_eq!(
+            result.end_of_a_sentence,
+            Some(String::from("deporte en el banco móvil."))
+        );
+        let result = segmenter.segment("Vale. Vamos.", true);
+        assert_eq!(result.end_of_a_sentence, None);
+        assert_eq!(result.sentences.unwrap().len(), 2);
+        assert_eq!(
+            sentences(&segmenter, "¿Qué...? Nada."),
+            vec!["¿Qué...?", "Nada."]
+        );
+    }
+
+    #[test]
+    fn test_capitalised_nouns() {
+        let text = "Haus gegangen. Dann";
+        let german = RuleSegmenter::german().segment(text, true);
+        assert_eq!(
+            german.end_of_a_sentence,
+            Some(String::from("Haus gegangen."))
+        );
+        assert_eq!(german.sentences, None);
+        assert_eq!(german.begin_of_a_sentence, Some(String::from("Dann")));
+        let fresh = RuleSegmenter::german().segment(text, false);
+        assert_eq!(fresh.sentences, Some(vec![String::from("Haus gegangen.")]));
+        let spanish = RuleSegmenter::spanish().segment(text, true);
+        assert_eq!(spanish.end_of_a_sentence, None);
+    }
+}
@@ -0,0 +1,245 @@
+use super::SubtitleSentence;
+use serde::de::{MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::ops::Deref;
+use std::time::Duration;
+
+///The sentences of a subtitle sorted by start time.
+///Dereferences to a slice, so the sentences can be iterated and indexed like a `Vec`.
+#[derive(Debug, Default)]
+pub struct SentenceList {
+    sentences: Vec<SubtitleSentence>,
+    ///max_end[i] is the latest end of the sentences up to i. It is used to find the sentences overlapping a time.
+    max_end: Vec<Duration>,
+}
+
+impl SentenceList {
+    pub fn new() -> SentenceList {
+        SentenceList {
+            sentences: Vec::new(),
+            max_end: Vec::new(),
+        }
+    }
+
+    ///Sorts the sentences by start time. Sentences with the same start time keep their order.
+    pub fn from_vec(mut sentences: Vec<SubtitleSentence>) -> SentenceList {
+        for sentence in sentences.iter_mut() {
+            migrate(sentence);
+        }
+        sentences.sort_by_key(|s| s.start);
+        let mut list = SentenceList {
+            sentences,
+            max_end: Vec::new(),
+        };
+        list.update_index(0);
+        list
+    }
+
+    ///Inserts the sentence after all sentences starting at the same time or earlier
+    pub fn push(&mut self, mut sentence: SubtitleSentence) {
+        migrate(&mut sentence);
+        let i = self
+            .sentences
+            .partition_point(|s| s.start <= sentence.start);
+        self.sentences.insert(i, sentence);
+        self.update_index(i);
+    }
+
+    pub fn into_vec(self) -> Vec<SubtitleSentence> {
+        self.sentences
+    }
+
+    ///Sentences which are shown at some time between `from` and `to`
+    pub fn sentences_between(&self, from: Duration, to: Duration) -> Vec<&SubtitleSentence> {
+        let first = self.max_end.partition_point(|end| *end < from);
+        let last = self.sentences.partition_point(|s| s.start <= to);
+        self.sentences[first..last.max(first)]
+            .iter()
+            .filter(|s| s.end >= from)
+            .collect()
+    }
+
+    ///The sentence shown at `time`. If sentences overlap, the one which started last.
+    pub fn sentence_at(&self, time: Duration) -> Option<&SubtitleSentence> {
+        self.position_at(time).map(|i| &self.sentences[i])
+    }
+
+    ///Index of the sentence returned by `sentence_at`
+    pub fn position_at(&self, time: Duration) -> Option<usize> {
+        let last = self.sentences.partition_point(|s| s.start <= time);
+        (0..last)
+            .rev()
+            .take_while(|i| self.max_end[*i] >= time)
+            .find(|i| self.sentences[*i].end >= time)
+    }
+
+    ///The sentence after the one at `index` with its index. Sentences of the same section start at the same time,
+    ///so the navigation goes by index and not by time.
+    pub fn next(&self, index: usize) -> Option<(usize, &SubtitleSentence)> {
+        let i = index.checked_add(1)?;
+        self.sentences.get(i).map(|s| (i, s))
+    }
+
+    ///The sentence before the one at `index` with its index
+    pub fn previous(&self, index: usize) -> Option<(usize, &SubtitleSentence)> {
+        let i = index.min(self.sentences.len()).checked_sub(1)?;
+        Some((i, &self.sentences[i]))
+    }
+
+    ///Recomputes `max_end` from the sentence at `first` on, the entries before it stay valid
+    fn update_index(&mut self, first: usize) {
+        let mut max_end = match first.checked_sub(1) {
+            Some(i) => self.max_end[i],
+            None => Duration::ZERO,
+        };
+        self.max_end.truncate(first);
+        for sentence in self.sentences[first..].iter() {
+            max_end = max_end.max(sentence.end);
+            self.max_end.push(max_end);
+        }
+    }
+}
+
+///Sentences stored before they had a start and an end only have the time_index
+fn migrate(sentence: &mut SubtitleSentence) {
+    if sentence.start == Duration::ZERO && sentence.time_index > 0 {
+        sentence.start = Duration::from_millis(sentence.time_index as u64);
+    }
+    if sentence.end < sentence.start {
+        sentence.end = sentence.start;
+    }
+}
+
+impl Deref for SentenceList {
+    type Target = [SubtitleSentence];
+
+    fn deref(&self) -> &[SubtitleSentence] {
+        &self.sentences
+    }
+}
+
+impl Serialize for SentenceList {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.sentences.serialize(serializer)
+    }
+}
+
+///Subtitles used to be stored with the sentences in a map with the second they started as key,
+///so we accept both a list and such a map
+struct SentenceListVisitor;
+
+impl<'de> Visitor<'de> for SentenceListVisitor {
+    type Value = SentenceList;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a list of sentences or a map of sentence lists")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<SentenceList, A::Error> {
+        let mut sentences = Vec::new();
+        while let Some(sentence) = seq.next_element()? {
+            sentences.push(sentence);
+        }
+        Ok(SentenceList::from_vec(sentences))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<SentenceList, A::Error> {
+        let mut sentences = Vec::new();
+        while let Some((_, list)) = map.next_entry::<String, Vec<SubtitleSentence>>()? {
+            sentences.extend(list);
+        }
+        Ok(SentenceList::from_vec(sentences))
+    }
+}
+
+impl<'de> Deserialize<'de> for SentenceList {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(SentenceListVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sentence(text: &str, start: u64, end: u64) -> SubtitleSentence {
+        SubtitleSentence {
+            time_index: start as u128,
+            sentence: String::from(text),
+            section_ids: vec![],
+            start: Duration::from_millis(start),
+            end: Duration::from_millis(end),
+        }
+    }
+
+    fn texts(sentences: Vec<&SubtitleSentence>) -> Vec<&str> {
+        sentences.iter().map(|s| s.sentence.as_str()).collect()
+    }
+
+    fn list() -> SentenceList {
+        let mut list = SentenceList::from_vec(vec![
+            sentence("c", 5000, 6000),
+            sentence("a", 1000, 9000),
+            sentence("b", 2000, 3000),
+        ]);
+        list.push(sentence("d", 2000, 2500));
+        list
+    }
+
+    #[test]
+    fn test_order() {
+        let list = list();
+        let order: Vec<&str> = list.iter().map(|s| s.sentence.as_str()).collect();
+        assert_eq!(order, vec!["a", "b", "d", "c"]);
+        assert_eq!(list.len(), 4);
+        assert_eq!(list[3].sentence, "c");
+        let ends: Vec<u128> = list.max_end.iter().map(|end| end.as_millis()).collect();
+        assert_eq!(ends, vec![9000, 9000, 9000, 9000]);
+        let mut list = SentenceList::new();
+        list.push(sentence("b", 2000, 3000));
+        list.push(sentence("c", 5000, 6000));
+        list.push(sentence("a", 1000, 2500));
+        let ends: Vec<u128> = list.max_end.iter().map(|end| end.as_millis()).collect();
+        assert_eq!(ends, vec![2500, 3000, 6000]);
+    }
+
+    #[test]
+    fn test_queries() {
+        let list = list();
+        let ms = Duration::from_millis;
+        assert_eq!(
+            texts(list.sentences_between(ms(2600), ms(5000))),
+            vec!["a", "b", "c"]
+        );
+        assert_eq!(
+            texts(list.sentences_between(ms(9500), ms(9600))),
+            Vec::<&str>::new()
+        );
+        assert_eq!(list.sentence_at(ms(2200)).unwrap().sentence, "d");
+        assert_eq!(list.sentence_at(ms(4000)).unwrap().sentence, "a");
+        assert!(list.sentence_at(ms(500)).is_none());
+        //"b" and "d" start at the same time
+        let b = list.position_at(ms(2700)).unwrap();
+        assert_eq!(list[b].sentence, "b");
+        let (d, next) = list.next(b).unwrap();
+        assert_eq!(next.sentence, "d");
+        assert_eq!(list.next(d).unwrap().1.sentence, "c");
+        assert!(list.next(3).is_none());
+        assert_eq!(list.previous(d).unwrap().0, b);
+        assert!(list.previous(0).is_none());
+    }
+
+    #[test]
+    fn test_serde_migration() {
+        let old = r#"{"2":[{"time_index":2500,"sentence":"b"}],"1":[{"time_index":1000,"sentence":"a"}]}"#;
+        let list: SentenceList = serde_json::from_str(old).unwrap();
+        assert_eq!(list[0].sentence, "a");
+        assert_eq!(list[1].start, Duration::from_millis(2500));
+        assert_eq!(list[1].end, Duration::from_millis(2500));
+        let json = serde_json::to_string(&list).unwrap();
+        assert!(json.starts_with(r#"[{"time_index":1000,"sentence":"a","#));
+        let list: SentenceList = serde_json::from_str(&json).unwrap();
+        assert_eq!(list.len(), 2);
+    }
+}
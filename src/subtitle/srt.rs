@@ -0,0 +1,304 @@
+use super::{annotations, markup, turns, ParseMode, SubtitleError, SubtitleSection};
+use crate::toolbox;
+
+///Parses the content of a SRT file into sections. The second element contains the skipped blocks in `ParseMode::Lenient`.
+pub fn parse(
+    contents: &str,
+    mode: ParseMode,
+) -> Result<(Vec<SubtitleSection>, Vec<SubtitleError>), SubtitleError> {
+    let contents = contents.trim_start_matches('\u{feff}');
+    let blocks = join_continuations(toolbox::split_blocks(contents));
+    if blocks.is_empty() {
+        return Err(SubtitleError::Empty);
+    }
+    let mut sections = Vec::new();
+    let mut warnings = Vec::new();
+    for (i, (first_line, lines)) in blocks.iter().enumerate() {
+        match parse_block(lines, i + 1, *first_line) {
+            Ok(Some(s)) => sections.push(s),
+            //a cue without text is skipped, lenient parsing reports it
+            Ok(None) if lines.len() < 3 && mode == ParseMode::Lenient => {
+                warnings.push(SubtitleError::MissingText {
+                    block: i + 1,
+                    line: first_line + 1,
+                    text: String::from(lines[1].trim()),
+                })
+            }
+            Ok(None) => (),
+            Err(e) => match mode {
+                ParseMode::Strict => return Err(e),
+                ParseMode::Lenient => warnings.push(e),
+            },
+        };
+    }
+    Ok((sections, warnings))
+}
+
+///A blank line inside the text of a cue splits it into two blocks.
+///Blocks which neither start with an id nor contain a time line are therefore appended to the previous block.
+fn join_continuations(blocks: Vec<(usize, Vec<&str>)>) -> Vec<(usize, Vec<&str>)> {
+    let mut result: Vec<(usize, Vec<&str>)> = Vec::new();
+    for (first_line, lines) in blocks {
+        let is_cue =
+            lines[0].trim().parse::<u64>().is_ok() || lines.iter().any(|line| line.contains("-->"));
+        match result.last_mut() {
+            Some((_, previous)) if !is_cue => previous.extend(lines),
+            _ => result.push((first_line, lines)),
+        }
+    }
+    result
+}
+
+///Parses the lines of a block consisting of the id, the time line and the text lines.
+///`block_number` and `first_line` are the position of the block in the file and are used for error reporting.
+///Returns `Ok(None)` if the block has no text or the text is empty after cleaning.
+pub fn parse_block(
+    lines: &[&str],
+    block_number: usize,
+    first_line: usize,
+) -> Result<Option<SubtitleSection>, SubtitleError> {
+    //first item is id
+    let id_line = lines.first().map(|line| line.trim()).unwrap_or("");
+    let id = id_line
+        .parse::<u64>()
+        .map_err(|_| SubtitleError::InvalidId {
+            block: block_number,
+            line: first_line,
+            text: String::from(id_line),
+        })?;
+    //second item is the time
+    let time_line = match lines.get(1) {
+        Some(line) => line.trim(),
+        None => {
+            return Err(SubtitleError::MissingTimes {
+                block: block_number,
+                line: first_line,
+                text: String::from(id_line),
+            })
+        }
+    };
+    let (from, to) = toolbox::get_times(time_line).ok_or_else(|| SubtitleError::InvalidTimes {
+        block: block_number,
+        line: first_line + 1,
+        text: String::from(time_line),
+    })?;
+    //3 item to last item is text
+    if lines.len() < 3 {
+        return Ok(None);
+    }
+    let mut text_lines: Vec<(Option<String>, String)> = lines[2..]
+        .iter()
+        .map(|line| (None, String::from(*line)))
+        .collect();
+    let annotations = annotations::extract(&mut text_lines);
+    let utterances = turns::split(&text_lines);
+    if utterances.is_empty() && annotations.is_empty() {
+        Ok(None)
+    } else {
+        let mut section = SubtitleSection::new();
+        section.id = id;
+        section.from = from;
+        section.to = to;
+        section.time_index = from.as_millis();
+        section.text = turns::text(&utterances);
+        section.speaker = utterances.iter().find_map(|u| u.speaker.clone());
+        section.utterances = utterances;
+        section.annotations = annotations;
+        section.raw_lines = lines[2..]
+            .iter()
+            .map(|line| String::from(line.trim()))
+            .collect();
+        let (spans, position) = markup::parse(&section.raw_lines);
+        section.set_spans(spans);
+        section.position = position;
+        Ok(Some(section))
+    }
+}
+
+///Writes the sections as SRT. The sections are sorted by start time and numbered from 1.
+///Sections with only annotations are written with their annotations.
+pub fn write(sections: &[SubtitleSection]) -> String {
+    let mut sorted: Vec<&SubtitleSection> = sections.iter().collect();
+    sorted.sort_by_key(|s| s.from);
+    let mut result = String::new();
+    for (i, section) in sorted.iter().enumerate() {
+        result.push_str(&format!(
+            "{}\n{} --> {}\n{}\n\n",
+            i + 1,
+            toolbox::format_time(section.from, ','),
+            toolbox::format_time(section.to, ','),
+            match section.export_spans() {
+                Some(spans) => markup::to_srt(spans, section.position),
+                None => section.export_text(),
+            }
+        ));
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    const BLOCKS: [&str; 3] = [
+        "1\n00:00:01,000 --> 00:00:02,000\n¿Vienes?",
+        "2\n00:00:03,000 --> 00:00:04,000\nNo.",
+        "10000\n02:46:40,000 --> 02:46:41,500\nAdiós.",
+    ];
+
+    fn assert_sections(contents: &str) {
+        let (sections, warnings) = parse(contents, ParseMode::Strict).unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(sections.len(), 3, "{:?}", contents);
+        assert_eq!(sections[0].id, 1);
+        assert_eq!(sections[0].text, "¿Vienes?");
+        assert_eq!(sections[1].from, Duration::from_millis(3000));
+        assert_eq!(sections[1].text, "No.");
+        assert_eq!(sections[2].id, 10000);
+        assert_eq!(sections[2].to, Duration::from_millis(10_001_500));
+        assert_eq!(sections[2].text, "Adiós.");
+    }
+
+    #[test]
+    fn test_lf() {
+        assert_sections(&format!("{}\n", BLOCKS.join("\n\n")));
+    }
+
+    #[test]
+    fn test_crlf() {
+        assert_sections(&format!("{}\r\n", BLOCKS.join("\n\n")).replace('\n', "\r\n"));
+    }
+
+    #[test]
+    fn test_cr() {
+        assert_sections(&format!("{}\r", BLOCKS.join("\n\n")).replace('\n', "\r"));
+    }
+
+    #[test]
+    fn test_mixed_line_endings() {
+        let contents = format!("{}\r\n\n{}\r\r{}", BLOCKS[0], BLOCKS[1], BLOCKS[2]);
+        assert_sections(&contents);
+    }
+
+    #[test]
+    fn test_blank_lines_with_whitespace() {
+        assert_sections(&format!("\n \n{}\n\n\n", BLOCKS.join("\n \t\n")));
+    }
+
+    #[test]
+    fn test_missing_trailing_newline() {
+        assert_sections(&BLOCKS.join("\n\n"));
+    }
+
+    #[test]
+    fn test_bom() {
+        assert_sections(&format!("\u{feff}\r\n{}", BLOCKS.join("\r\n\r\n")));
+    }
+
+    #[test]
+    fn test_blank_line_inside_text() {
+        let contents = "1\n00:00:01,000 --> 00:00:02,000\nHola,\n\nque tal.\n\n2\n00:00:03,000 --> 00:00:04,000\nBien.\n";
+        let (sections, _) = parse(contents, ParseMode::Strict).unwrap();
+        assert_eq!(sections.len(), 2);
+        assert_eq!(sections[0].text, "Hola, que tal.");
+    }
+
+    #[test]
+    fn test_multi_line_text() {
+        let contents =
+            "1\n00:00:01,000 --> 00:00:02,000\n- ¿Vienes?\n- No.\n<i>Tengo que</i>\ntrabajar.\n";
+        let (sections, _) = parse(contents, ParseMode::Strict).unwrap();
+        assert_eq!(sections[0].text, "¿Vienes? No. Tengo que trabajar.");
+        assert_eq!(sections[0].utterances.len(), 2);
+        assert_eq!(sections[0].utterances[1].text, "No. Tengo que trabajar.");
+        assert_eq!(
+            sections[0].raw_lines,
+            vec!["- ¿Vienes?", "- No.", "<i>Tengo que</i>", "trabajar."]
+        );
+    }
+
+    #[test]
+    fn test_number_in_text() {
+        //a number on its own line isn't taken for the start of a new block
+        let contents = "1\n00:00:01,000 --> 00:00:02,000\nEl año\n2017\n\n2\n00:00:03,000 --> 00:00:04,000\nBien.";
+        let (sections, _) = parse(contents, ParseMode::Strict).unwrap();
+        assert_eq!(sections.len(), 2);
+        assert_eq!(sections[0].text, "El año 2017");
+    }
+
+    #[test]
+    fn test_write() {
+        let (sections, _) = parse(&BLOCKS.join("\n\n"), ParseMode::Strict).unwrap();
+        let mut reversed: Vec<SubtitleSection> = sections.into_iter().rev().collect();
+        reversed[0].id = 42;
+        let written = write(&reversed);
+        assert!(written.starts_with("1\n00:00:01,000 --> 00:00:02,000\n¿Vienes?\n\n2\n"));
+        assert!(written.ends_with("3\n02:46:40,000 --> 02:46:41,500\nAdiós.\n\n"));
+        let (written_sections, _) = parse(&written, ParseMode::Strict).unwrap();
+        assert_eq!(written_sections.len(), 3);
+        assert_eq!(written_sections[2].id, 3);
+        assert_eq!(written_sections[2].to, Duration::from_millis(10_001_500));
+    }
+
+    #[test]
+    fn test_write_edited() {
+        let contents = "1\n00:00:01,000 --> 00:00:02,000\n<i>¿Vienes?</i>\n\n2\n00:00:03,000 --> 00:00:04,000\n<i>No.</i>\n";
+        let (mut sections, _) = parse(contents, ParseMode::Strict).unwrap();
+        assert!(write(&sections).contains("\n<i>¿Vienes?</i>\n"));
+        //a changed text is written without the old formatting
+        sections[0].text = String::from("¿Vienes o no?");
+        //changed spans are written as long as the text is the same
+        sections[1].spans[0].style.italic = false;
+        sections[1].spans[0].style.bold = true;
+        let written = write(&sections);
+        assert!(written.contains("\n¿Vienes o no?\n"));
+        assert!(written.contains("\n<b>No.</b>\n"));
+    }
+
+    #[test]
+    fn test_invalid_time_lines() {
+        for time_line in [
+            "٠٠:٠٠:٠١,٠٠٠ --> ٠٠:٠٠:٠٢,٠٠٠",
+            "00:00:01,000 --> 00:00:02,000 und so",
+            "Ab 00:00:01,000 --> 00:00:02,000",
+        ] {
+            let contents = format!("1\n{}\nHola.\n", time_line);
+            assert!(matches!(
+                parse(&contents, ParseMode::Strict),
+                Err(SubtitleError::InvalidTimes { .. })
+            ));
+        }
+        let contents = "1\n00:00:01,000 --> 00:00:02,000  X1:100 X2:600 Y1:50 Y2:80\nHola.\n";
+        let (sections, _) = parse(contents, ParseMode::Strict).unwrap();
+        assert_eq!(sections[0].to, Duration::from_millis(2000));
+    }
+
+    #[test]
+    fn test_cue_without_text() {
+        let contents = format!(
+            "{}\n\n5\n00:00:02,000 --> 00:00:03,000\n\n{}",
+            BLOCKS[0],
+            BLOCKS[1..].join("\n\n")
+        );
+        assert_sections(&contents);
+        let (sections, warnings) = parse(&contents, ParseMode::Lenient).unwrap();
+        assert_eq!(sections.len(), 3);
+        assert!(matches!(
+            warnings.as_slice(),
+            [SubtitleError::MissingText {
+                block: 2,
+                line: 6,
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn test_empty() {
+        assert!(matches!(
+            parse("\r\n \r\n", ParseMode::Strict),
+            Err(SubtitleError::Empty)
+        ));
+    }
+}
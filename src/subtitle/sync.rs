@@ -0,0 +1,339 @@
+use super::{Subtitle, TimeMapping};
+use std::collections::HashMap;
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub struct SyncOptions {
+    ///largest offset between the two tracks which is searched for
+    pub max_offset: Duration,
+    ///cue boundaries closer than `tolerance` are regarded as the same
+    pub tolerance: Duration,
+    ///framerate ratios which are tried to detect a drift, 1.0 means no drift. Ratios which are not positive are ignored.
+    pub framerate_ratios: Vec<f64>,
+}
+
+impl Default for SyncOptions {
+    fn default() -> Self {
+        SyncOptions::new()
+    }
+}
+
+impl SyncOptions {
+    pub fn new() -> SyncOptions {
+        SyncOptions {
+            max_offset: Duration::from_secs(60),
+            tolerance: Duration::from_millis(200),
+            //the common conversions between 23.976, 24, 25 and 29.97 fps
+            framerate_ratios: vec![
+                1.0,
+                25.0 / 23.976,
+                23.976 / 25.0,
+                25.0 / 24.0,
+                24.0 / 25.0,
+                24.0 / 23.976,
+                23.976 / 24.0,
+                29.97 / 25.0,
+                25.0 / 29.97,
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SyncEstimate {
+    ///maps the times of the subtitle to the times of the reference
+    pub mapping: TimeMapping,
+    ///share of the cue boundaries of the subtitle which match a boundary of the reference after the mapping
+    pub confidence: f64,
+}
+
+///Start and end times of the cues in milliseconds. Starts after a pause are weighted more,
+///as the pattern of pauses is the same in all languages.
+struct Boundaries {
+    starts: Vec<(f64, f64)>,
+    ends: Vec<(f64, f64)>,
+}
+
+///Pause before a cue after which its start gets a higher weight
+const PAUSE: u128 = 1500;
+///Width of the histogram bins of the offset search in milliseconds
+const BIN: f64 = 100.0;
+
+///Estimates the constant offset and the drift between two tracks of the same video, e.g. in two languages.
+///Each framerate ratio is tried with an offset found by cross-correlating the cue boundaries,
+///then the best candidate is refined by a linear regression over the matching boundaries.
+///Returns None if one of the tracks has no sections or there is no positive framerate ratio.
+pub fn estimate(
+    reference: &Subtitle,
+    subtitle: &Subtitle,
+    options: &SyncOptions,
+) -> Option<SyncEstimate> {
+    if reference.sections.is_empty() || subtitle.sections.is_empty() {
+        return None;
+    }
+    let reference = boundaries(reference);
+    let subtitle = boundaries(subtitle);
+    let tolerance = options.tolerance.as_millis() as f64;
+    let max_offset = options.max_offset.as_millis() as f64;
+    let mut best: Option<(TimeMapping, f64)> = None;
+    for factor in options
+        .framerate_ratios
+        .iter()
+        .filter(|factor| factor.is_finite() && **factor > 0.0)
+    {
+        let offset = correlate(&reference, &subtitle, *factor, max_offset, tolerance);
+        let mapping = TimeMapping {
+            factor: *factor,
+            offset,
+        };
+        let score = matching_weight(&reference, &subtitle, &mapping, tolerance);
+        let better = match best {
+            Some((_, best_score)) => score > best_score,
+            None => true,
+        };
+        if better {
+            best = Some((mapping, score));
+        }
+    }
+    let (mut mapping, _) = best?;
+    //the regression corrects small drifts which aren't covered by the framerate ratios
+    for _ in 0..3 {
+        match regression(&reference, &subtitle, &mapping, tolerance * 2.0) {
+            Some(refined) => mapping = refined,
+            None => break,
+        }
+    }
+    let total: f64 = subtitle
+        .starts
+        .iter()
+        .chain(subtitle.ends.iter())
+        .map(|(_, w)| w)
+        .sum();
+    Some(SyncEstimate {
+        mapping,
+        confidence: matching_weight(&reference, &subtitle, &mapping, tolerance) / total,
+    })
+}
+
+fn boundaries(subtitle: &Subtitle) -> Boundaries {
+    let mut times: Vec<(u128, u128)> = subtitle
+        .sections
+        .iter()
+        .map(|s| (s.from.as_millis(), s.to.as_millis()))
+        .collect();
+    times.sort_unstable();
+    let mut starts = Vec::new();
+    let mut previous_end: Option<u128> = None;
+    for (from, to) in times.iter() {
+        let after_pause = match previous_end {
+            Some(end) => *from >= end + PAUSE,
+            None => true,
+        };
+        starts.push((*from as f64, if after_pause { 2.0 } else { 1.0 }));
+        previous_end = Some(previous_end.map_or(*to, |end| end.max(*to)));
+    }
+    let mut ends: Vec<(f64, f64)> = times.iter().map(|(_, to)| (*to as f64, 1.0)).collect();
+    ends.sort_by(|a, b| a.0.total_cmp(&b.0));
+    Boundaries { starts, ends }
+}
+
+///Every pair of boundaries of the same kind votes for the offset between them.
+///Returns the offset with the most votes within `tolerance`.
+fn correlate(
+    reference: &Boundaries,
+    subtitle: &Boundaries,
+    factor: f64,
+    max_offset: f64,
+    tolerance: f64,
+) -> f64 {
+    let mut votes: HashMap<i64, f64> = HashMap::new();
+    let pairs = [
+        (&reference.starts, &subtitle.starts),
+        (&reference.ends, &subtitle.ends),
+    ];
+    for (reference, subtitle) in pairs.iter() {
+        for (time, weight) in subtitle.iter() {
+            let time = time * factor;
+            let first = reference.partition_point(|(t, _)| *t < time - max_offset);
+            for (reference_time, reference_weight) in reference[first..]
+                .iter()
+                .take_while(|(t, _)| *t <= time + max_offset)
+            {
+                let bin = ((reference_time - time) / BIN).round() as i64;
+                *votes.entry(bin).or_insert(0.0) += weight * reference_weight;
+            }
+        }
+    }
+    //the votes of the neighbouring bins within the tolerance are added up
+    let radius = (tolerance / BIN).round() as i64;
+    let best_bin = votes
+        .keys()
+        .map(|bin| {
+            let sum: f64 = (bin - radius..=bin + radius)
+                .filter_map(|b| votes.get(&b))
+                .sum();
+            (*bin, sum)
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1).then(b.0.abs().cmp(&a.0.abs())))
+        .map(|(bin, _)| bin)
+        .unwrap_or(0);
+    best_bin as f64 * BIN
+}
+
+///Closest reference boundary of the same kind for each mapped boundary of the subtitle
+fn matches<'a>(
+    reference: &'a Boundaries,
+    subtitle: &'a Boundaries,
+    mapping: &'a TimeMapping,
+    tolerance: f64,
+) -> impl Iterator<Item = (f64, f64, f64)> + 'a {
+    let pairs = vec![
+        (&reference.starts, &subtitle.starts),
+        (&reference.ends, &subtitle.ends),
+    ];
+    pairs.into_iter().flat_map(move |(reference, subtitle)| {
+        subtitle.iter().filter_map(move |(time, weight)| {
+            let mapped = mapping.factor * time + mapping.offset;
+            let i = reference.partition_point(|(t, _)| *t < mapped);
+            let candidates = [i.checked_sub(1), Some(i)];
+            candidates
+                .iter()
+                .flatten()
+                .filter_map(|&i| reference.get(i))
+                .map(|(t, _)| *t)
+                .min_by(|a, b| (a - mapped).abs().total_cmp(&(b - mapped).abs()))
+                .filter(|t| (t - mapped).abs() <= tolerance)
+                .map(|t| (*time, t, *weight))
+        })
+    })
+}
+
+fn matching_weight(
+    reference: &Boundaries,
+    subtitle: &Boundaries,
+    mapping: &TimeMapping,
+    tolerance: f64,
+) -> f64 {
+    matches(reference, subtitle, mapping, tolerance)
+        .map(|(_, _, weight)| weight)
+        .sum()
+}
+
+///Least squares fit of the reference times on the subtitle times of the matching boundaries.
+///Returns None if the fit would reverse the order of the times.
+fn regression(
+    reference: &Boundaries,
+    subtitle: &Boundaries,
+    mapping: &TimeMapping,
+    tolerance: f64,
+) -> Option<TimeMapping> {
+    let points: Vec<(f64, f64)> = matches(reference, subtitle, mapping, tolerance)
+        .map(|(x, y, _)| (x, y))
+        .collect();
+    if points.len() < 2 {
+        return None;
+    }
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let covariance: f64 = points
+        .iter()
+        .map(|(x, y)| (x - mean_x) * (y - mean_y))
+        .sum();
+    let variance: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    if variance == 0.0 {
+        return None;
+    }
+    let factor = covariance / variance;
+    if !factor.is_finite() || factor <= 0.0 {
+        return None;
+    }
+    Some(TimeMapping {
+        factor,
+        offset: mean_y - factor * mean_x,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(mapping: &TimeMapping, original: u128, expected: u128) {
+        let mapped = mapping.map_millis(original) as i128;
+        assert!(
+            (mapped - expected as i128).abs() <= 100,
+            "{} mapped to {} instead of {}",
+            original,
+            mapped,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_offset_and_drift() {
+        let reference = Subtitle::load("./resources/Subtitles/papel_S01E01_en.srt").unwrap();
+        let mut subtitle = Subtitle::load("./resources/Subtitles/papel_S01E01_en.srt").unwrap();
+        //a release for 25 fps which starts 2.5 seconds later
+        assert!(subtitle.rescale(23.976, 25.0));
+        subtitle.shift(2500);
+        let estimate = estimate(&reference, &subtitle, &SyncOptions::new()).unwrap();
+        assert!(estimate.confidence > 0.9, "{:?}", estimate);
+        for (original, changed) in reference.sections.iter().zip(subtitle.sections.iter()) {
+            assert_close(&estimate.mapping, changed.time_index, original.time_index);
+        }
+    }
+
+    #[test]
+    fn test_two_languages() {
+        let es = Subtitle::load("./resources/Subtitles/papel_S01E01_es.srt").unwrap();
+        let mut en = Subtitle::load("./resources/Subtitles/papel_S01E01_en.srt").unwrap();
+        let original = estimate(&es, &en, &SyncOptions::new()).unwrap();
+        en.shift(-4000);
+        let shifted = estimate(&es, &en, &SyncOptions::new()).unwrap();
+        assert!(shifted.confidence > 0.3, "{:?}", shifted);
+        for time in [60_000, 1_800_000, 3_000_000].iter() {
+            assert_close(
+                &shifted.mapping,
+                *time - 4000,
+                original.mapping.map_millis(*time),
+            );
+        }
+    }
+
+    #[test]
+    fn test_empty() {
+        let subtitle = Subtitle::load("./resources/test/test.vtt").unwrap();
+        let empty = Subtitle::new(String::from("empty"));
+        assert!(estimate(&subtitle, &empty, &SyncOptions::new()).is_none());
+    }
+
+    #[test]
+    fn test_invalid_ratios() {
+        let reference = Subtitle::load("./resources/Subtitles/papel_S01E01_en.srt").unwrap();
+        let mut subtitle = Subtitle::load("./resources/Subtitles/papel_S01E01_en.srt").unwrap();
+        subtitle.shift(1000);
+        let mut options = SyncOptions::new();
+        options.framerate_ratios = vec![f64::NAN, 0.0, -1.0, f64::INFINITY];
+        assert!(estimate(&reference, &subtitle, &options).is_none());
+        options.framerate_ratios.push(1.0);
+        let estimate = estimate(&reference, &subtitle, &options).unwrap();
+        assert!(estimate.mapping.factor > 0.0);
+        assert_close(&estimate.mapping, 61_000, 60_000);
+    }
+
+    #[test]
+    fn test_regression_keeps_order() {
+        let boundaries = |times: &[f64]| Boundaries {
+            starts: times.iter().map(|t| (*t, 1.0)).collect(),
+            ends: Vec::new(),
+        };
+        //a mapping which reverses the times matches the later boundary to the earlier one
+        let reversed = TimeMapping {
+            factor: -1.0,
+            offset: 3000.0,
+        };
+        let reference = boundaries(&[1900.0, 2000.0]);
+        let subtitle = boundaries(&[1000.0, 1100.0]);
+        assert!(regression(&reference, &subtitle, &reversed, 50.0).is_none());
+    }
+}
@@ -0,0 +1,122 @@
+use std::time::Duration;
+
+///Linear mapping of subtitle times: `new = factor * old + offset`, both in milliseconds
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct TimeMapping {
+    pub factor: f64,
+    pub offset: f64,
+}
+
+impl TimeMapping {
+    ///Moves all times by `milliseconds`, which can be negative
+    pub fn shift(milliseconds: i64) -> TimeMapping {
+        TimeMapping {
+            factor: 1.0,
+            offset: milliseconds as f64,
+        }
+    }
+
+    ///Converts times of a subtitle made for a video with `from_fps` to a video with `to_fps`,
+    ///e.g. from a 25 fps PAL release to a 23.976 fps release.
+    ///Returns None unless both framerates are finite and greater than 0.
+    pub fn rescale(from_fps: f64, to_fps: f64) -> Option<TimeMapping> {
+        let valid = |fps: f64| fps.is_finite() && fps > 0.0;
+        if !valid(from_fps) || !valid(to_fps) {
+            return None;
+        }
+        Some(TimeMapping {
+            factor: from_fps / to_fps,
+            offset: 0.0,
+        })
+    }
+
+    ///Maps `a` to `x` and `b` to `y`. Returns None if `a` and `b` are equal or the order would be reversed.
+    pub fn from_points(a: Duration, x: Duration, b: Duration, y: Duration) -> Option<TimeMapping> {
+        let (a, x, b, y) = (
+            a.as_millis() as f64,
+            x.as_millis() as f64,
+            b.as_millis() as f64,
+            y.as_millis() as f64,
+        );
+        let factor = (y - x) / (b - a);
+        if !factor.is_finite() || factor <= 0.0 {
+            return None;
+        }
+        Some(TimeMapping {
+            factor,
+            offset: x - factor * a,
+        })
+    }
+
+    ///Maps the time in milliseconds. Times before 0 become 0.
+    pub fn map_millis(&self, millis: u128) -> u128 {
+        (self.factor * millis as f64 + self.offset).round().max(0.0) as u128
+    }
+
+    ///Maps the time. Times beyond the range of `Duration::from_millis` become its maximum.
+    pub fn map(&self, time: Duration) -> Duration {
+        let millis = self.map_millis(time.as_millis());
+        Duration::from_millis(millis.min(u64::MAX as u128) as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mappings() {
+        let second = Duration::from_secs(1);
+        assert_eq!(
+            TimeMapping::shift(-1500).map(Duration::from_secs(3)),
+            Duration::from_millis(1500)
+        );
+        assert_eq!(
+            TimeMapping::shift(-1500).map(second),
+            Duration::from_millis(0)
+        );
+        let rescale = TimeMapping::rescale(25.0, 23.976).unwrap();
+        assert_eq!(
+            rescale.map(Duration::from_secs(3600)),
+            Duration::from_millis(3_753_754)
+        );
+        let resync = TimeMapping::from_points(
+            Duration::from_secs(10),
+            Duration::from_secs(12),
+            Duration::from_secs(110),
+            Duration::from_secs(122),
+        )
+        .unwrap();
+        assert_eq!(resync.map(Duration::from_secs(10)), Duration::from_secs(12));
+        assert_eq!(resync.map(Duration::from_secs(60)), Duration::from_secs(67));
+        assert_eq!(
+            TimeMapping::from_points(second, second, second, second * 2),
+            None
+        );
+        assert_eq!(
+            TimeMapping::from_points(second, second * 2, second * 2, second),
+            None
+        );
+    }
+
+    #[test]
+    fn test_invalid_framerates() {
+        for (from_fps, to_fps) in [
+            (0.0, 25.0),
+            (25.0, 0.0),
+            (-25.0, 25.0),
+            (f64::NAN, 25.0),
+            (25.0, f64::INFINITY),
+        ] {
+            assert_eq!(TimeMapping::rescale(from_fps, to_fps), None);
+        }
+        let huge = TimeMapping {
+            factor: 1e30,
+            offset: 0.0,
+        };
+        assert_eq!(
+            huge.map(Duration::from_secs(1)),
+            Duration::from_millis(u64::MAX)
+        );
+    }
+}
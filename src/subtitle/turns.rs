@@ -0,0 +1,189 @@
+use super::SubtitleSection;
+use crate::toolbox;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+lazy_static! {
+    //dash at the start of a line, which marks a new speaker
+    static ref LEADING_DASH_REGEX: Regex = Regex::new(r#"^[-–—]+\s*"#).unwrap();
+    //dash after the end of a sentence within a line like "¿Vienes? - No."
+    static ref INLINE_DASH_REGEX: Regex = Regex::new(r#"[.!?…]["»”’)]*(\s+[-–—]+\s*)"#).unwrap();
+}
+
+///The text one speaker says in a section
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Utterance {
+    ///index of the speaker turn within the subtitle, counted from 0.
+    ///Utterances of consecutive sections share the index as long as the speaker doesn't change.
+    pub turn: usize,
+    ///speaker if the format names it, e.g. WebVTT `<v Speaker>` tags or the ASS `Name` column
+    #[serde(default)]
+    pub speaker: Option<String>,
+    pub text: String,
+}
+
+///Splits the text lines of a cue into utterances. A new turn starts with a dialogue dash and when the named speaker changes.
+///If only the first line of a cue starts with a dash ("- Yo no seré,\nasí que te olvidas."), the cue is one turn
+///whose line wraps.
+///The turns are counted within the cue: the first utterance has turn 1 if it is marked as a new turn, otherwise 0.
+pub fn split(lines: &[(Option<String>, String)]) -> Vec<Utterance> {
+    let cleaned: Vec<(&Option<String>, bool, Vec<String>)> = lines
+        .iter()
+        .map(|(speaker, line)| {
+            let line = toolbox::clean_content_string(line);
+            let line = line.trim();
+            let dashed = LEADING_DASH_REGEX.is_match(line);
+            let line = LEADING_DASH_REGEX.replace(line, "");
+            let parts = split_inline(&line).into_iter().map(String::from).collect();
+            (speaker, dashed, parts)
+        })
+        .collect();
+    let mut utterances: Vec<Utterance> = Vec::new();
+    let mut turn = 0;
+    for (speaker, dashed, parts) in cleaned {
+        for (j, part) in parts.iter().enumerate() {
+            let text = toolbox::special_language_replacements(part.trim());
+            if text.is_empty() {
+                continue;
+            }
+            let speaker_changed = match utterances.last() {
+                Some(previous) => speaker.is_some() && previous.speaker != *speaker,
+                None => false,
+            };
+            let new_turn = j > 0 || dashed || speaker_changed;
+            match utterances.last_mut() {
+                Some(previous) if !new_turn => {
+                    previous.text.push(' ');
+                    previous.text.push_str(&text);
+                }
+                _ => {
+                    if new_turn {
+                        turn += 1;
+                    }
+                    utterances.push(Utterance {
+                        turn,
+                        speaker: speaker.clone(),
+                        text,
+                    });
+                }
+            }
+        }
+    }
+    utterances
+}
+
+///Splits a line at the dashes following the end of a sentence
+fn split_inline(line: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    for caps in INLINE_DASH_REGEX.captures_iter(line) {
+        let dash = caps.get(1).unwrap();
+        parts.push(&line[start..dash.start()]);
+        start = dash.end();
+    }
+    parts.push(&line[start..]);
+    parts
+}
+
+///Joins the utterances to the text of the section
+pub fn text(utterances: &[Utterance]) -> String {
+    utterances
+        .iter()
+        .map(|u| u.text.as_str())
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+///Replaces the turns counted within each section by turns counted within the whole subtitle
+pub fn number(sections: &mut [SubtitleSection]) {
+    let mut turn = 0;
+    let mut speaker: Option<String> = None;
+    let mut first = true;
+    for section in sections.iter_mut() {
+        let mut last_turn = 0;
+        for utterance in section.utterances.iter_mut() {
+            let speaker_changed = utterance.speaker.is_some() && utterance.speaker != speaker;
+            if (utterance.turn > last_turn || speaker_changed) && !first {
+                turn += 1;
+            }
+            first = false;
+            last_turn = utterance.turn;
+            speaker = utterance.speaker.clone();
+            utterance.turn = turn;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(lines: &[&str]) -> Vec<(Option<String>, String)> {
+        lines
+            .iter()
+            .map(|line| (None, String::from(*line)))
+            .collect()
+    }
+
+    fn texts(utterances: &[Utterance]) -> Vec<(usize, &str)> {
+        utterances
+            .iter()
+            .map(|u| (u.turn, u.text.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_dashes() {
+        let utterances = split(&lines(&["- ¿Vienes? - No.", "Tengo que trabajar."]));
+        assert_eq!(
+            texts(&utterances),
+            vec![(1, "¿Vienes?"), (2, "No. Tengo que trabajar.")]
+        );
+        let utterances = split(&lines(&["-Yo Urano no seré,", "así que te olvidas."]));
+        assert_eq!(
+            texts(&utterances),
+            vec![(1, "Yo Urano no seré, así que te olvidas.")]
+        );
+        let utterances = split(&lines(&["Te lo dije, ¿no?", "- Sí. El e-mail."]));
+        assert_eq!(
+            texts(&utterances),
+            vec![(0, "Te lo dije, ¿no?"), (1, "Sí. El e-mail.")]
+        );
+        let utterances = split(&lines(&["No sé si", "<i>venir - o no.</i>"]));
+        assert_eq!(texts(&utterances), vec![(0, "No sé si venir - o no.")]);
+    }
+
+    #[test]
+    fn test_speakers() {
+        let tokio = Some(String::from("Tokio"));
+        let rio = Some(String::from("Río"));
+        let utterances = split(&[
+            (tokio.clone(), String::from("Hola,")),
+            (tokio, String::from("Río.")),
+            (rio, String::from("Hola.")),
+        ]);
+        assert_eq!(texts(&utterances), vec![(0, "Hola, Río."), (1, "Hola.")]);
+    }
+
+    #[test]
+    fn test_number() {
+        let mut sections: Vec<SubtitleSection> = vec![
+            vec!["- ¿Vienes?", "- No, tengo que"],
+            vec!["trabajar."],
+            vec!["- Vale."],
+        ]
+        .into_iter()
+        .map(|l| {
+            let mut section = SubtitleSection::new();
+            section.utterances = split(&lines(&l));
+            section
+        })
+        .collect();
+        number(&mut sections);
+        let turns: Vec<usize> = sections
+            .iter()
+            .flat_map(|s| s.utterances.iter().map(|u| u.turn))
+            .collect();
+        assert_eq!(turns, vec![0, 1, 1, 2]);
+    }
+}
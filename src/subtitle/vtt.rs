@@ -0,0 +1,328 @@
+use super::{annotations, markup, turns, ParseMode, Position, SubtitleError, SubtitleSection};
+use crate::toolbox;
+use regex::Regex;
+use std::time::Duration;
+
+lazy_static! {
+    //the hour is optional in WebVTT and the timing line can be followed by cue settings
+    static ref VTT_TIME_REGEX: Regex = Regex::new(
+        r#"^(?:(\d{2,}):)?(\d{2}):(\d{2})\.(\d{3})[ \t]+-->[ \t]+(?:(\d{2,}):)?(\d{2}):(\d{2})\.(\d{3})"#
+    )
+    .unwrap();
+    //<v Speaker> or <v.class Speaker>
+    static ref VOICE_REGEX: Regex = Regex::new(r#"<v(?:\.[^ \t>]+)*[ \t]+([^>]+)>"#).unwrap();
+    //all other cue tags as well as the karaoke timestamps <00:00:01.000>
+    static ref TAG_REGEX: Regex = Regex::new(
+        r#"</?(?:c|i|b|u|v|ruby|rt|lang)(?:[.\s][^>]*)?>|<(?:\d{2,}:)?\d{2}:\d{2}\.\d{3}>"#
+    )
+    .unwrap();
+}
+
+///Parses the content of a WebVTT file into sections. The second element contains the skipped cues in `ParseMode::Lenient`.
+pub fn parse(
+    contents: &str,
+    mode: ParseMode,
+) -> Result<(Vec<SubtitleSection>, Vec<SubtitleError>), SubtitleError> {
+    let contents = contents.trim_start_matches('\u{feff}');
+    let blocks = toolbox::split_blocks(contents);
+    //the first block is the header which has to start with WEBVTT
+    let header = match blocks.first() {
+        Some((_, lines)) => lines[0],
+        None => return Err(SubtitleError::Empty),
+    };
+    if !is_header(header) {
+        return Err(SubtitleError::InvalidHeader {
+            text: String::from(header),
+        });
+    }
+    let mut sections = Vec::new();
+    let mut warnings = Vec::new();
+    let mut cue_number = 0;
+    for (i, (first_line, lines)) in blocks.iter().enumerate().skip(1) {
+        //comments, style sheets and region definitions don't contain any text
+        if ["NOTE", "STYLE", "REGION"]
+            .iter()
+            .any(|keyword| starts_with_keyword(lines[0], keyword))
+        {
+            continue;
+        }
+        cue_number += 1;
+        match parse_cue(lines, i + 1, *first_line, cue_number) {
+            Ok(Some(s)) => sections.push(s),
+            Ok(None) => (),
+            Err(e) => match mode {
+                ParseMode::Strict => return Err(e),
+                ParseMode::Lenient => warnings.push(e),
+            },
+        }
+    }
+    Ok((sections, warnings))
+}
+
+///Returns true if the line is a valid WebVTT header
+pub fn is_header(line: &str) -> bool {
+    starts_with_keyword(line.trim_start_matches('\u{feff}'), "WEBVTT")
+}
+
+fn starts_with_keyword(line: &str, keyword: &str) -> bool {
+    match line.strip_prefix(keyword) {
+        Some(rest) => rest.is_empty() || rest.starts_with(' ') || rest.starts_with('\t'),
+        None => false,
+    }
+}
+
+///A cue consists of an optional identifier, the timing line with optional settings and the text
+fn parse_cue(
+    lines: &[&str],
+    block_number: usize,
+    first_line: usize,
+    cue_number: u64,
+) -> Result<Option<SubtitleSection>, SubtitleError> {
+    let (identifier, time_index) = if lines[0].contains("-->") {
+        (None, 0)
+    } else {
+        (Some(lines[0].trim()), 1)
+    };
+    let time_line = match lines.get(time_index) {
+        Some(line) => line.trim(),
+        None => {
+            return Err(SubtitleError::MissingTimes {
+                block: block_number,
+                line: first_line,
+                text: String::from(lines[0]),
+            })
+        }
+    };
+    let (from, to) = get_times(time_line).ok_or_else(|| SubtitleError::InvalidTimes {
+        block: block_number,
+        line: first_line + time_index,
+        text: String::from(time_line),
+    })?;
+    //numeric identifiers are kept as id, otherwise the cues are numbered
+    let id = identifier
+        .and_then(|identifier| identifier.parse::<u64>().ok())
+        .unwrap_or(cue_number);
+    let payload = &lines[time_index + 1..];
+    let speaker = payload
+        .iter()
+        .find_map(|line| VOICE_REGEX.captures(line))
+        .map(|caps| String::from(caps[1].trim()));
+    //a voice tag names the speaker until the next one
+    let mut voice = None;
+    let mut text_lines: Vec<(Option<String>, String)> = payload
+        .iter()
+        .map(|line| {
+            if let Some(caps) = VOICE_REGEX.captures(line) {
+                voice = Some(String::from(caps[1].trim()));
+            }
+            (voice.clone(), clean_cue_text(line))
+        })
+        .collect();
+    //cues without text are allowed in WebVTT
+    let annotations = annotations::extract(&mut text_lines);
+    let utterances = turns::split(&text_lines);
+    if utterances.is_empty() && annotations.is_empty() {
+        Ok(None)
+    } else {
+        let mut section = SubtitleSection::new();
+        section.id = id;
+        section.from = from;
+        section.to = to;
+        section.time_index = from.as_millis();
+        section.text = turns::text(&utterances);
+        section.utterances = utterances;
+        section.annotations = annotations;
+        section.raw_lines = payload
+            .iter()
+            .map(|line| String::from(line.trim()))
+            .collect();
+        let (mut spans, _) = markup::parse(&section.raw_lines);
+        for span in spans.iter_mut() {
+            span.text = decode_entities(&span.text);
+        }
+        section.set_spans(spans);
+        section.position = get_position(time_line);
+        section.speaker = speaker;
+        Ok(Some(section))
+    }
+}
+
+///Parses `00:00:01.000 --> 00:00:02.000 align:start` where the hours are optional
+pub fn get_times(time_line: &str) -> Option<(Duration, Duration)> {
+    let caps = VTT_TIME_REGEX.captures(time_line)?;
+    let number = |i: usize| match caps.get(i) {
+        Some(m) => m.as_str().parse::<u64>().ok(),
+        None => Some(0),
+    };
+    //the hours have no upper limit, so the time can overflow
+    let millis = |first: usize| {
+        number(first)?
+            .checked_mul(60)?
+            .checked_add(number(first + 1)?)?
+            .checked_mul(60)?
+            .checked_add(number(first + 2)?)?
+            .checked_mul(1000)?
+            .checked_add(number(first + 3)?)
+    };
+    Some((
+        Duration::from_millis(millis(1)?),
+        Duration::from_millis(millis(5)?),
+    ))
+}
+
+///Reads the `line` cue setting: lines counted from the top or percentages below 50 put the cue at the top
+fn get_position(time_line: &str) -> Option<Position> {
+    let line = time_line
+        .split_whitespace()
+        .find_map(|setting| setting.strip_prefix("line:"))?;
+    let line = line.split(',').next()?;
+    let top = match line.strip_suffix('%') {
+        Some(percent) => percent.parse::<f64>().ok()? < 50.0,
+        None => line.parse::<i64>().ok()? >= 0,
+    };
+    Some(Position::Alignment(if top { 8 } else { 2 }))
+}
+
+///Removes the cue tags and decodes the escaped characters
+fn clean_cue_text(line: &str) -> String {
+    decode_entities(&TAG_REGEX.replace_all(line, ""))
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&nbsp;", " ")
+        .replace("&lrm;", "")
+        .replace("&rlm;", "")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+///Writes the sections as WebVTT. The sections are sorted by start time and numbered from 1.
+pub fn write(sections: &[SubtitleSection]) -> String {
+    write_with(sections, None, escape_cue_text)
+}
+
+///Writes the sections as WebVTT with an optional style sheet.
+///`payload` turns the text of a section into the cue text and has to escape it.
+pub fn write_with<F>(sections: &[SubtitleSection], style: Option<&str>, payload: F) -> String
+where
+    F: Fn(&str) -> String,
+{
+    let mut sorted: Vec<&SubtitleSection> = sections.iter().collect();
+    sorted.sort_by_key(|s| s.from);
+    let mut result = String::from("WEBVTT\n\n");
+    if let Some(style) = style {
+        result.push_str(&format!("STYLE\n{}\n\n", style.trim()));
+    }
+    for (i, section) in sorted.iter().enumerate() {
+        let settings = match section.position {
+            Some(Position::Alignment(alignment)) if alignment >= 7 => " line:0",
+            _ => "",
+        };
+        let text = match section.export_spans() {
+            Some(spans) => markup::to_vtt(spans, escape_cue_text),
+            None => payload(&section.export_text()),
+        };
+        result.push_str(&format!(
+            "{}\n{} --> {}{}\n{}\n\n",
+            i + 1,
+            toolbox::format_time(section.from, '.'),
+            toolbox::format_time(section.to, '.'),
+            settings,
+            text
+        ));
+    }
+    result
+}
+
+///Escapes the characters which would be taken for markup
+pub fn escape_cue_text(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vtt_file() {
+        let contents = std::fs::read_to_string("./resources/test/test.vtt").unwrap();
+        let (sections, warnings) = parse(&contents, ParseMode::Strict).unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(sections.len(), 4);
+        assert_eq!(sections[0].id, 1);
+        assert_eq!(sections[0].from, Duration::from_millis(6520));
+        assert_eq!(sections[0].to, Duration::from_millis(7760));
+        assert_eq!(sections[0].text, "¡Quieto o disparo!");
+        assert_eq!(sections[0].speaker, None);
+        //cue identifier which is not a number
+        assert_eq!(sections[1].id, 2);
+        assert_eq!(sections[1].text, "Me llamo Tokio.");
+        assert_eq!(sections[1].speaker, Some(String::from("Tokio")));
+        //hours omitted
+        assert_eq!(sections[2].from, Duration::from_millis(65000));
+        assert_eq!(sections[2].text, "Tú & yo.");
+        assert_eq!(sections[3].speaker, Some(String::from("Profesor")));
+        assert_eq!(sections[3].text, "Buenos días.");
+    }
+
+    #[test]
+    fn test_write() {
+        let contents = std::fs::read_to_string("./resources/test/test.vtt").unwrap();
+        let (sections, _) = parse(&contents, ParseMode::Strict).unwrap();
+        let written = write(&sections);
+        assert!(written.starts_with(
+            "WEBVTT\n\n1\n00:00:06.520 --> 00:00:07.760\n<i>¡Quieto o disparo!</i>\n\n2\n"
+        ));
+        assert!(written.contains(" line:0\n<c.yellow>Tú &amp; yo.</c>\n"));
+        let (written_sections, _) = parse(&written, ParseMode::Strict).unwrap();
+        assert_eq!(written_sections.len(), 4);
+        assert_eq!(written_sections[2].id, 3);
+        assert_eq!(written_sections[2].from, Duration::from_millis(65000));
+        assert_eq!(written_sections[2].text, "Tú & yo.");
+        assert_eq!(written_sections[2].spans, sections[2].spans);
+        assert_eq!(written_sections[2].position, Some(Position::Alignment(8)));
+    }
+
+    #[test]
+    fn test_invalid_header() {
+        let result = parse("1\n00:00:01.000 --> 00:00:02.000\nHola.", ParseMode::Strict);
+        assert!(matches!(result, Err(SubtitleError::InvalidHeader { .. })));
+    }
+
+    #[test]
+    fn test_lenient_invalid_times() {
+        let contents =
+            "WEBVTT\n\n00:01,000 --> 00:02,000\nHola.\n\n00:03.000 --> 00:04.000\nAdiós.\n";
+        assert!(matches!(
+            parse(contents, ParseMode::Strict),
+            Err(SubtitleError::InvalidTimes {
+                block: 2,
+                line: 3,
+                ..
+            })
+        ));
+        let (sections, warnings) = parse(contents, ParseMode::Lenient).unwrap();
+        assert_eq!(sections.len(), 1);
+        assert_eq!(sections[0].text, "Adiós.");
+        assert_eq!(warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_hours_overflow() {
+        assert_eq!(
+            get_times("100:00:00.000 --> 100:00:01.000"),
+            Some((Duration::from_secs(360_000), Duration::from_secs(360_001)))
+        );
+        let contents = "WEBVTT\n\n99999999999999999999:00:01.000 --> 99999999999999999999:00:02.000\nHola.\n\n5124095576030432:00:00.000 --> 5124095576030432:00:01.000\nAdiós.\n";
+        let (sections, warnings) = parse(contents, ParseMode::Lenient).unwrap();
+        assert!(sections.is_empty());
+        assert_eq!(warnings.len(), 2);
+        assert!(matches!(
+            warnings[0],
+            SubtitleError::InvalidTimes { block: 2, .. }
+        ));
+    }
+}
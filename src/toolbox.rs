@@ -8,19 +8,21 @@ lazy_static! {
         Regex::new(&String::from(r#"<i>"#)).unwrap(),
         Regex::new(&String::from(r#"</i>"#)).unwrap(),
         Regex::new(&String::from(r#"""#)).unwrap(),
-        Regex::new(&String::from(r#"-"#)).unwrap(),
-        Regex::new(&String::from(r#"\(.{1,30}\)"#)).unwrap(),
+        //ASS/SSA override blocks like {\i1} or {\pos(320,50)}, which also appear in SRT files as {\an8}
+        Regex::new(&String::from(r#"\{[^}]*\}"#)).unwrap(),
     ];
-    static ref TIME_REGEX: Regex =
-        Regex::new(r#"(\d{2}):(\d{2}):(\d{2}).(\d{3}) --> (\d{2}):(\d{2}):(\d{2}).(\d{3})"#)
-            .unwrap();
-    static ref SENTENCE_REGEX: Regex = Regex::new(r#"(¿?¡?[A-Z][^\.!?]*[\.!?])"#).unwrap();
+    //the whole line, optionally followed by the display coordinates some SRT files have like "X1:100 X2:600 Y1:50 Y2:80".
+    //\d would also match digits of other scripts.
+    static ref TIME_REGEX: Regex = Regex::new(
+        r#"^\s*([0-9]{2}):([0-9]{2}):([0-9]{2})[,.]([0-9]{3}) --> ([0-9]{2}):([0-9]{2}):([0-9]{2})[,.]([0-9]{3})(?:\s+[XY][12]:[0-9]+)*\s*$"#
+    )
+    .unwrap();
     static ref SPECIAL_LANGUAGE_REGEX: Vec<(Regex, String)> = vec![
         (Regex::new(r#"¿([^,?]+)\?, "#).unwrap(), String::from("")),
-        (Regex::new(r#"(\.\.\.)"#).unwrap(), String::from(" - "))
     ];
 }
 
+#[allow(clippy::needless_range_loop)]
 pub fn clean_content_string(input: &str) -> String {
     let mut result = String::from(input);
     for i in 0..CLEAN_REGEX_VEC.len() {
@@ -33,6 +35,7 @@ pub fn clean_content_string(input: &str) -> String {
     result
 }
 
+#[allow(clippy::needless_range_loop)]
 pub fn special_language_replacements(input: &str) -> String {
     let mut result = String::from(input);
     for i in 0..SPECIAL_LANGUAGE_REGEX.len() {
@@ -47,58 +50,100 @@ pub fn special_language_replacements(input: &str) -> String {
     result
 }
 
-pub fn get_text(lines: Vec<&str>) -> Option<String> {
-    let mut text = String::new();
-    match lines.get(0) {
-        Some(line) => {
-            text.push_str(&line.trim());
-        }
-        None => return None,
-    };
-    text.push(' ');
-    match lines.get(1) {
-        Some(line) => {
-            text.push_str(&line.trim());
-        }
-        None => {
-            return Some(text);
-        }
-    };
-    text.push(' ');
-    match lines.get(2) {
-        Some(line) => {
-            text.push_str(&line.trim());
-            return Some(text);
+///Splits the input into lines, which can end with "\n", "\r\n" or "\r".
+///A line ending at the end of the input doesn't start a new line.
+pub fn split_lines(input: &str) -> Vec<&str> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+    let mut chars = input.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\n' => {
+                lines.push(&input[start..i]);
+                start = i + 1;
+            }
+            '\r' => {
+                lines.push(&input[start..i]);
+                start = i + 1;
+                if let Some((_, '\n')) = chars.peek() {
+                    chars.next();
+                    start += 1;
+                }
+            }
+            _ => (),
         }
-        None => {
-            return Some(text);
+    }
+    if start < input.len() {
+        lines.push(&input[start..]);
+    }
+    lines
+}
+
+///Splits the input into blocks of lines separated by blank lines.
+///Each block comes with the number of its first line, starting at 1.
+pub fn split_blocks(input: &str) -> Vec<(usize, Vec<&str>)> {
+    let mut blocks = Vec::new();
+    let mut current: Option<(usize, Vec<&str>)> = None;
+    for (i, line) in split_lines(input).into_iter().enumerate() {
+        if line.trim().is_empty() {
+            if let Some(block) = current.take() {
+                blocks.push(block);
+            }
+        } else {
+            current
+                .get_or_insert_with(|| (i + 1, Vec::new()))
+                .1
+                .push(line);
         }
-    };
+    }
+    if let Some(block) = current {
+        blocks.push(block);
+    }
+    blocks
 }
-///r#"(\d{2}):(\d{2}):(\d{2}).(\d{3}) --> (\d{2}):(\d{2}):(\d{2}).(\d{3})"#
-pub fn get_times(time_line: &str) -> (Duration, Duration) {
-    let caps = TIME_REGEX.captures(&time_line).unwrap();
-    let from_hour = caps.get(1).unwrap().as_str().parse::<u64>().unwrap();
-    let from_minute = caps.get(2).unwrap().as_str().parse::<u64>().unwrap() + from_hour * 60;
-    let from_second = caps.get(3).unwrap().as_str().parse::<u64>().unwrap() + from_minute * 60;
-    let from_milli = caps.get(4).unwrap().as_str().parse::<u64>().unwrap() + from_second * 1000;
-    let to_hour = caps.get(5).unwrap().as_str().parse::<u64>().unwrap();
-    let to_minute = caps.get(6).unwrap().as_str().parse::<u64>().unwrap() + to_hour * 60;
-    let to_second = caps.get(7).unwrap().as_str().parse::<u64>().unwrap() + to_minute * 60;
-    let to_milli = caps.get(8).unwrap().as_str().parse::<u64>().unwrap() + to_second * 1000;
-    let d_from = Duration::from_millis(from_milli);
-    assert_eq!(from_second, d_from.as_secs());
-    let d_to = Duration::from_millis(to_milli);
-    assert_eq!(to_second, d_to.as_secs());
-    (
+
+///Joins all text lines of a section with a space. Returns None if there is no line.
+pub fn get_text(lines: Vec<&str>) -> Option<String> {
+    if lines.is_empty() {
+        return None;
+    }
+    Some(
+        lines
+            .iter()
+            .map(|line| line.trim())
+            .collect::<Vec<&str>>()
+            .join(" "),
+    )
+}
+///r#"(\d{2}):(\d{2}):(\d{2}),(\d{3}) --> (\d{2}):(\d{2}):(\d{2}),(\d{3})"#
+///Returns None if the line isn't a time range.
+pub fn get_times(time_line: &str) -> Option<(Duration, Duration)> {
+    let caps = TIME_REGEX.captures(time_line)?;
+    let mut numbers = [0u64; 8];
+    for (i, number) in numbers.iter_mut().enumerate() {
+        *number = caps[i + 1].parse().ok()?;
+    }
+    let from_minute = numbers[1] + numbers[0] * 60;
+    let from_second = numbers[2] + from_minute * 60;
+    let from_milli = numbers[3] + from_second * 1000;
+    let to_minute = numbers[5] + numbers[4] * 60;
+    let to_second = numbers[6] + to_minute * 60;
+    let to_milli = numbers[7] + to_second * 1000;
+    Some((
         Duration::from_millis(from_milli),
         Duration::from_millis(to_milli),
-    )
+    ))
 }
 
-pub fn extract_sentences(input: String) -> Vec<String> {
-    SENTENCE_REGEX
-        .captures_iter(&input)
-        .map(|x| format!("{}", &x[1]))
-        .collect()
+///Formats the duration as `00:00:00,000`. The separator before the milliseconds is `,` in SRT and `.` in WebVTT.
+pub fn format_time(time: Duration, separator: char) -> String {
+    let millis = time.as_millis();
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        separator,
+        millis % 1000
+    )
 }
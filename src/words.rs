@@ -0,0 +1,12 @@
+pub mod frequency;
+pub mod index;
+pub mod lemmatizer;
+pub mod tokenizer;
+
+pub use self::frequency::{
+    FrequencyBands, FrequencyEntry, FrequencyList, FrequencyListError, FrequencyListFormat,
+    FrequencyLists,
+};
+pub use self::index::{Occurrence, WordIndex};
+pub use self::lemmatizer::{Lemma, Lemmatizer, Lexicon, LexiconError, SuffixRule};
+pub use self::tokenizer::{Token, TokenKind, Tokenizer};
@@ -0,0 +1,437 @@
+use super::tokenizer::normalize;
+use crate::flashcard::{FlashCard, Language, WordFrequency};
+use crate::subtitle::encoding;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+///Path of the list of the 10000 most frequent Spanish word forms of the RAE CREA corpus
+pub const SPANISH_CREA_LIST: &str = "./resources/Wordlists/10000_formas_Spanish.txt";
+
+///Layout of a frequency list file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrequencyListFormat {
+    ///RAE CREA lists like `     1.\tde\t9,999,518 \t 65545.55` below a header line:
+    ///rank, word, absolute frequency with thousands separators and frequency per million words
+    Crea,
+    ///SUBTLEX lists: tab separated columns with a header naming the columns `Word` and `FREQcount`
+    Subtlex,
+    ///OpenSubtitles lists like `de 2345678`: a word and its count per line, ordered by count
+    OpenSubtitles,
+}
+
+///Errors which can occur while reading a frequency list. `line` starts at 1.
+#[derive(Debug)]
+pub enum FrequencyListError {
+    ///The file could not be read
+    Io(io::Error),
+    ///The file does not contain a single word
+    Empty,
+    ///The header doesn't name the columns the format requires
+    InvalidHeader { text: String },
+    ///The line doesn't have the columns of the format
+    InvalidLine { line: usize, text: String },
+}
+
+impl fmt::Display for FrequencyListError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FrequencyListError::Io(e) => write!(f, "could not read frequency list: {}", e),
+            FrequencyListError::Empty => write!(f, "frequency list does not contain any word"),
+            FrequencyListError::InvalidHeader { text } => {
+                write!(f, "invalid header \"{}\"", text)
+            }
+            FrequencyListError::InvalidLine { line, text } => {
+                write!(f, "line {}: invalid entry \"{}\"", line, text)
+            }
+        }
+    }
+}
+
+impl Error for FrequencyListError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            FrequencyListError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for FrequencyListError {
+    fn from(e: io::Error) -> FrequencyListError {
+        FrequencyListError::Io(e)
+    }
+}
+
+///A word of a frequency list
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrequencyEntry {
+    ///position in the list, starting at 1 for the most frequent word
+    pub rank: usize,
+    pub word: String,
+    ///number of times the word appears in the corpus
+    pub count: u64,
+}
+
+///Words of a language ordered by their frequency
+#[derive(Debug)]
+pub struct FrequencyList {
+    pub language: Language,
+    pub entries: Vec<FrequencyEntry>,
+    ///index of the entry of each lowercase word
+    words: HashMap<String, usize>,
+}
+
+impl FrequencyList {
+    ///Builds the list from entries ordered by rank
+    pub fn new(language: Language, entries: Vec<FrequencyEntry>) -> FrequencyList {
+        let mut words = HashMap::new();
+        for (i, entry) in entries.iter().enumerate() {
+            //a word can appear twice with different case, the more frequent one counts
+            words.entry(normalize(&entry.word)).or_insert(i);
+        }
+        FrequencyList {
+            language,
+            entries,
+            words,
+        }
+    }
+
+    ///Reads a list in any encoding supported by `encoding::decode`
+    pub fn load(
+        path: &str,
+        language: Language,
+        format: FrequencyListFormat,
+    ) -> Result<FrequencyList, FrequencyListError> {
+        let (contents, _) = encoding::read_to_string(path)?;
+        FrequencyList::parse(&contents, language, format)
+    }
+
+    ///Reads the Spanish CREA list shipped with the repository
+    pub fn spanish() -> Result<FrequencyList, FrequencyListError> {
+        FrequencyList::load(
+            SPANISH_CREA_LIST,
+            Language::Spanish,
+            FrequencyListFormat::Crea,
+        )
+    }
+
+    pub fn parse(
+        contents: &str,
+        language: Language,
+        format: FrequencyListFormat,
+    ) -> Result<FrequencyList, FrequencyListError> {
+        let entries = match format {
+            FrequencyListFormat::Crea => parse_crea(contents)?,
+            FrequencyListFormat::Subtlex => parse_subtlex(contents)?,
+            FrequencyListFormat::OpenSubtitles => parse_open_subtitles(contents)?,
+        };
+        if entries.is_empty() {
+            return Err(FrequencyListError::Empty);
+        }
+        Ok(FrequencyList::new(language, entries))
+    }
+
+    ///The entry of the word, compared ignoring case
+    pub fn get(&self, word: &str) -> Option<&FrequencyEntry> {
+        self.words.get(&normalize(word)).map(|&i| &self.entries[i])
+    }
+
+    pub fn rank(&self, word: &str) -> Option<usize> {
+        self.get(word).map(|entry| entry.rank)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+///Lines which are neither empty nor a header, with their line number
+fn data_lines(contents: &str) -> impl Iterator<Item = (usize, &str)> {
+    contents
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim_end_matches('\r')))
+        .filter(|(_, line)| !line.trim().is_empty())
+}
+
+fn parse_count(text: &str) -> Option<u64> {
+    text.trim().replace(',', "").parse().ok()
+}
+
+fn parse_crea(contents: &str) -> Result<Vec<FrequencyEntry>, FrequencyListError> {
+    let mut entries = Vec::new();
+    for (line, text) in data_lines(contents) {
+        let columns: Vec<&str> = text.split('\t').collect();
+        let rank = columns[0].trim().trim_end_matches('.');
+        if entries.is_empty() && rank.parse::<usize>().is_err() {
+            //the header "Orden\tFrec.absoluta\tFrec.normalizada"
+            continue;
+        }
+        let invalid = || FrequencyListError::InvalidLine {
+            line,
+            text: String::from(text),
+        };
+        if columns.len() < 3 {
+            return Err(invalid());
+        }
+        entries.push(FrequencyEntry {
+            rank: rank.parse().map_err(|_| invalid())?,
+            word: String::from(columns[1].trim()),
+            count: parse_count(columns[2]).ok_or_else(invalid)?,
+        });
+    }
+    Ok(entries)
+}
+
+fn parse_subtlex(contents: &str) -> Result<Vec<FrequencyEntry>, FrequencyListError> {
+    let mut lines = data_lines(contents);
+    let header = match lines.next() {
+        Some((_, header)) => header,
+        None => return Ok(vec![]),
+    };
+    let columns: Vec<String> = header
+        .split('\t')
+        .map(|c| c.trim().trim_matches('"').to_lowercase())
+        .collect();
+    let column = |names: &[&str]| columns.iter().position(|c| names.contains(&c.as_str()));
+    let (word_column, count_column) = match (
+        column(&["word", "spelling"]),
+        column(&["freqcount", "freq", "frequency"]),
+    ) {
+        (Some(word), Some(count)) => (word, count),
+        _ => {
+            return Err(FrequencyListError::InvalidHeader {
+                text: String::from(header),
+            })
+        }
+    };
+    let mut entries = Vec::new();
+    for (line, text) in lines {
+        let values: Vec<&str> = text
+            .split('\t')
+            .map(|v| v.trim().trim_matches('"'))
+            .collect();
+        let invalid = || FrequencyListError::InvalidLine {
+            line,
+            text: String::from(text),
+        };
+        let word = values.get(word_column).ok_or_else(invalid)?;
+        let count = values
+            .get(count_column)
+            .and_then(|c| parse_count(c))
+            .ok_or_else(invalid)?;
+        entries.push(FrequencyEntry {
+            rank: 0,
+            word: String::from(*word),
+            count,
+        });
+    }
+    //SUBTLEX lists are sorted alphabetically
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.count));
+    for (i, entry) in entries.iter_mut().enumerate() {
+        entry.rank = i + 1;
+    }
+    Ok(entries)
+}
+
+fn parse_open_subtitles(contents: &str) -> Result<Vec<FrequencyEntry>, FrequencyListError> {
+    let mut entries = Vec::new();
+    for (line, text) in data_lines(contents) {
+        let (word, count) = match text.trim().rsplit_once(char::is_whitespace) {
+            Some((word, count)) => (word.trim(), parse_count(count)),
+            None => ("", None),
+        };
+        match count {
+            Some(count) if !word.is_empty() => entries.push(FrequencyEntry {
+                rank: entries.len() + 1,
+                word: String::from(word),
+                count,
+            }),
+            _ => {
+                return Err(FrequencyListError::InvalidLine {
+                    line,
+                    text: String::from(text),
+                })
+            }
+        }
+    }
+    Ok(entries)
+}
+
+///The highest rank of each frequency band. Words ranked below `low` and words missing from the list are `VeryLow`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrequencyBands {
+    pub very_high: usize,
+    pub high: usize,
+    pub medium: usize,
+    pub low: usize,
+}
+
+impl FrequencyBands {
+    pub fn new() -> FrequencyBands {
+        FrequencyBands {
+            very_high: 500,
+            high: 1500,
+            medium: 4000,
+            low: 10000,
+        }
+    }
+
+    pub fn classify(&self, rank: Option<usize>) -> WordFrequency {
+        match rank {
+            Some(rank) if rank <= self.very_high => WordFrequency::VeryHigh,
+            Some(rank) if rank <= self.high => WordFrequency::High,
+            Some(rank) if rank <= self.medium => WordFrequency::Medium,
+            Some(rank) if rank <= self.low => WordFrequency::Low,
+            _ => WordFrequency::VeryLow,
+        }
+    }
+}
+
+impl Default for FrequencyBands {
+    fn default() -> Self {
+        FrequencyBands::new()
+    }
+}
+
+///Frequency lists of several languages and the bands to classify their words
+#[derive(Debug, Default)]
+pub struct FrequencyLists {
+    pub bands: FrequencyBands,
+    lists: HashMap<Language, FrequencyList>,
+}
+
+impl FrequencyLists {
+    pub fn new() -> FrequencyLists {
+        FrequencyLists {
+            bands: FrequencyBands::new(),
+            lists: HashMap::new(),
+        }
+    }
+
+    ///Adds the list for its language and replaces the previous list of the language
+    pub fn insert(&mut self, list: FrequencyList) {
+        self.lists.insert(list.language, list);
+    }
+
+    pub fn get(&self, language: Language) -> Option<&FrequencyList> {
+        self.lists.get(&language)
+    }
+
+    ///The band of the word. `Undefined` if there is no list for the language.
+    pub fn frequency(&self, word: &str, language: Language) -> WordFrequency {
+        match self.lists.get(&language) {
+            Some(list) => self.bands.classify(list.rank(word)),
+            None => WordFrequency::Undefined,
+        }
+    }
+
+    ///Sets the frequency of the card from the list of its language
+    pub fn classify(&self, card: &mut FlashCard) {
+        card.frequency = self.frequency(&card.word, card.language);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crea() {
+        let list = FrequencyList::spanish().unwrap();
+        assert_eq!(list.len(), 10000);
+        assert_eq!(
+            list.get("Que"),
+            Some(&FrequencyEntry {
+                rank: 3,
+                word: String::from("que"),
+                count: 4681839
+            })
+        );
+        assert_eq!(list.rank("normalización"), Some(10000));
+        assert_eq!(list.rank("tokio"), Some(8245));
+        assert_eq!(list.rank("nairobi"), None);
+        let error = FrequencyList::parse(
+            "Orden\tFrec.absoluta \t Frec.normalizada \r\n     1.\tde\r\n",
+            Language::Spanish,
+            FrequencyListFormat::Crea,
+        );
+        assert!(matches!(
+            error,
+            Err(FrequencyListError::InvalidLine { line: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn test_other_formats() {
+        let subtlex =
+            "Word\tFREQcount\tCDcount\nthe\t1501908\t8388\nyou\t2134713\t8381\nThe\t12\t3\n";
+        let list =
+            FrequencyList::parse(subtlex, Language::English, FrequencyListFormat::Subtlex).unwrap();
+        assert_eq!(list.rank("you"), Some(1));
+        assert_eq!(list.rank("THE"), Some(2));
+        let open_subtitles = "ich 2345678\ndu 2000000\n";
+        let list = FrequencyList::parse(
+            open_subtitles,
+            Language::German,
+            FrequencyListFormat::OpenSubtitles,
+        )
+        .unwrap();
+        assert_eq!(list.get("du").map(|e| e.count), Some(2000000));
+        assert!(matches!(
+            FrequencyList::parse(
+                "Wort\tAnzahl\n",
+                Language::German,
+                FrequencyListFormat::Subtlex
+            ),
+            Err(FrequencyListError::InvalidHeader { .. })
+        ));
+    }
+
+    #[test]
+    fn test_classify() {
+        let mut lists = FrequencyLists::new();
+        lists.bands = FrequencyBands {
+            very_high: 1,
+            high: 2,
+            medium: 3,
+            low: 4,
+        };
+        let open_subtitles = "de 100\nla 90\nque 80\nel 70\nen 60\n";
+        lists.insert(
+            FrequencyList::parse(
+                open_subtitles,
+                Language::Spanish,
+                FrequencyListFormat::OpenSubtitles,
+            )
+            .unwrap(),
+        );
+        let frequencies: Vec<WordFrequency> = ["de", "la", "que", "el", "en", "tokio"]
+            .iter()
+            .map(|word| lists.frequency(word, Language::Spanish))
+            .collect();
+        assert_eq!(
+            frequencies,
+            vec![
+                WordFrequency::VeryHigh,
+                WordFrequency::High,
+                WordFrequency::Medium,
+                WordFrequency::Low,
+                WordFrequency::VeryLow,
+                WordFrequency::VeryLow
+            ]
+        );
+        let mut card = FlashCard::new(String::from("La"), Language::Spanish);
+        lists.classify(&mut card);
+        assert_eq!(card.frequency, WordFrequency::High);
+        let mut card = FlashCard::new(String::from("the"), Language::English);
+        lists.classify(&mut card);
+        assert_eq!(card.frequency, WordFrequency::Undefined);
+    }
+}
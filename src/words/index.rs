@@ -0,0 +1,147 @@
+use super::tokenizer::{TokenKind, Tokenizer};
+use crate::subtitle::{Subtitle, SubtitleSection};
+use std::collections::HashMap;
+use std::time::Duration;
+
+///A place where a word is said
+#[derive(Debug, Clone, PartialEq)]
+pub struct Occurrence {
+    ///index of the sentence in `Subtitle::sentences`
+    pub sentence: usize,
+    ///start of the section the word is in
+    pub time: Duration,
+    ///id of the section the word is in
+    pub section_id: u64,
+    ///the word as written in the sentence
+    pub form: String,
+}
+
+///Maps each word of a subtitle to the places it appears.
+///The words are lowercase. Contractions and verbs with clitics are found under their parts as well,
+///e.g. "dámelo" under "dámelo", "da", "me" and "lo".
+#[derive(Debug, Default)]
+pub struct WordIndex {
+    words: HashMap<String, Vec<Occurrence>>,
+}
+
+impl WordIndex {
+    pub fn new() -> WordIndex {
+        WordIndex {
+            words: HashMap::new(),
+        }
+    }
+
+    pub fn from_subtitle(subtitle: &Subtitle, tokenizer: &Tokenizer) -> WordIndex {
+        let mut index = WordIndex::new();
+        let sections: HashMap<u64, &SubtitleSection> =
+            subtitle.sections.iter().map(|s| (s.id, s)).collect();
+        for (i, sentence) in subtitle.sentences.iter().enumerate() {
+            //the words of the sections in the order they are said, to find the section of each word of the sentence
+            let section_words: Vec<(&SubtitleSection, String)> = sentence
+                .section_ids
+                .iter()
+                .filter_map(|id| sections.get(id))
+                .flat_map(|section| {
+                    tokenizer
+                        .tokenize(&section.text)
+                        .into_iter()
+                        .filter(|t| t.kind == TokenKind::Word)
+                        .map(move |t| (*section, t.normalized))
+                })
+                .collect();
+            let mut next_word = 0;
+            let mut section = section_words.first().map(|(s, _)| *s);
+            for token in tokenizer.tokenize(&sentence.sentence) {
+                if token.kind != TokenKind::Word {
+                    continue;
+                }
+                if let Some(found) = section_words[next_word..]
+                    .iter()
+                    .position(|(_, word)| *word == token.normalized)
+                {
+                    next_word += found + 1;
+                    section = Some(section_words[next_word - 1].0);
+                }
+                let occurrence = Occurrence {
+                    sentence: i,
+                    time: section.map_or(sentence.start, |s| s.from),
+                    section_id: section.map_or(0, |s| s.id),
+                    form: token.text,
+                };
+                for part in token.parts {
+                    if part != token.normalized {
+                        index.add(part, occurrence.clone());
+                    }
+                }
+                index.add(token.normalized, occurrence);
+            }
+        }
+        index
+    }
+
+    fn add(&mut self, word: String, occurrence: Occurrence) {
+        let occurrences = self.words.entry(word).or_default();
+        //a word said twice in a sentence like "no, no" is one occurrence per section
+        if !occurrences
+            .iter()
+            .any(|o| o.sentence == occurrence.sentence && o.section_id == occurrence.section_id)
+        {
+            occurrences.push(occurrence);
+        }
+    }
+
+    ///The places the word appears in order of the sentences. The word is compared ignoring case.
+    pub fn occurrences(&self, word: &str) -> &[Occurrence] {
+        self.words
+            .get(&super::tokenizer::normalize(word))
+            .map_or(&[], |o| o.as_slice())
+    }
+
+    ///All words of the subtitle in no particular order
+    pub fn words(&self) -> impl Iterator<Item = &str> {
+        self.words.keys().map(|w| w.as_str())
+    }
+
+    ///Number of different words
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flashcard::Language;
+    use crate::subtitle::ParseMode;
+
+    fn subtitle() -> Subtitle {
+        let srt = "1\n00:00:00,000 --> 00:00:05,000\nDámelo, por favor.\n\n\
+                   2\n00:00:10,000 --> 00:00:15,000\nNo, no lo\n\n\
+                   3\n00:00:20,000 --> 00:00:25,000\ntengo. ¿Y el coche del padre?\n";
+        Subtitle::from_srt_string("test", srt, ParseMode::Strict).unwrap()
+    }
+
+    #[test]
+    fn test_occurrences() {
+        let index = WordIndex::from_subtitle(&subtitle(), &Tokenizer::new(Language::Spanish));
+        let no = index.occurrences("No");
+        assert_eq!(no.len(), 1);
+        assert_eq!(no[0].section_id, 2);
+        let tengo = index.occurrences("tengo");
+        assert_eq!(tengo[0].time, Duration::from_secs(20));
+        assert_eq!(tengo[0].sentence, no[0].sentence);
+        let lo = index.occurrences("lo");
+        assert_eq!(
+            lo.iter().map(|o| o.form.as_str()).collect::<Vec<&str>>(),
+            vec!["Dámelo", "lo"]
+        );
+        assert_eq!(index.occurrences("da")[0].section_id, 1);
+        assert_eq!(index.occurrences("el").len(), 1);
+        assert_eq!(index.occurrences("del")[0].form, "del");
+        assert!(index.occurrences("coches").is_empty());
+    }
+}
@@ -0,0 +1,472 @@
+use super::tokenizer::{normalize, Token, TokenKind};
+use crate::flashcard::{Language, PartOfSpeech};
+use crate::subtitle::encoding;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+///Path of the Spanish lexicon shipped with the repository
+pub const SPANISH_LEXICON: &str = "./resources/Lexicon/lexicon_Spanish.txt";
+
+///Errors which can occur while reading a lexicon. `line` starts at 1.
+#[derive(Debug)]
+pub enum LexiconError {
+    ///The file could not be read
+    Io(io::Error),
+    ///The line doesn't have the form `form<TAB>lemma<TAB>TAG` with a Universal Dependencies tag
+    InvalidLine { line: usize, text: String },
+}
+
+impl fmt::Display for LexiconError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LexiconError::Io(e) => write!(f, "could not read lexicon: {}", e),
+            LexiconError::InvalidLine { line, text } => {
+                write!(f, "line {}: invalid entry \"{}\"", line, text)
+            }
+        }
+    }
+}
+
+impl Error for LexiconError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LexiconError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for LexiconError {
+    fn from(e: io::Error) -> LexiconError {
+        LexiconError::Io(e)
+    }
+}
+
+///The dictionary form of a word
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lemma {
+    pub lemma: String,
+    pub part_of_speech: PartOfSpeech,
+}
+
+impl Lemma {
+    pub fn new(lemma: &str, part_of_speech: PartOfSpeech) -> Lemma {
+        Lemma {
+            lemma: String::from(lemma),
+            part_of_speech,
+        }
+    }
+}
+
+///Word forms with their lemmas, read from lines like `tomé<TAB>tomar<TAB>VERB`
+#[derive(Debug, Default)]
+pub struct Lexicon {
+    ///the readings of each form, the most frequent first
+    forms: HashMap<String, Vec<Lemma>>,
+    lemmas: HashSet<(String, PartOfSpeech)>,
+}
+
+impl Lexicon {
+    pub fn new() -> Lexicon {
+        Lexicon {
+            forms: HashMap::new(),
+            lemmas: HashSet::new(),
+        }
+    }
+
+    pub fn load(path: &str) -> Result<Lexicon, LexiconError> {
+        let (contents, _) = encoding::read_to_string(path)?;
+        Lexicon::parse(&contents)
+    }
+
+    ///Reads tab separated lines of form, lemma and tag. Empty lines and lines starting with `#` are skipped.
+    pub fn parse(contents: &str) -> Result<Lexicon, LexiconError> {
+        let mut lexicon = Lexicon::new();
+        for (i, text) in contents.lines().enumerate() {
+            let text = text.trim_end_matches('\r');
+            if text.trim().is_empty() || text.starts_with('#') {
+                continue;
+            }
+            let columns: Vec<&str> = text.split('\t').map(|c| c.trim()).collect();
+            match columns.as_slice() {
+                [form, lemma, tag] if !form.is_empty() && !lemma.is_empty() => {
+                    match PartOfSpeech::from_tag(tag) {
+                        Some(part_of_speech) => {
+                            lexicon.insert(form, Lemma::new(lemma, part_of_speech))
+                        }
+                        None => {
+                            return Err(LexiconError::InvalidLine {
+                                line: i + 1,
+                                text: String::from(text),
+                            })
+                        }
+                    }
+                }
+                _ => {
+                    return Err(LexiconError::InvalidLine {
+                        line: i + 1,
+                        text: String::from(text),
+                    })
+                }
+            }
+        }
+        Ok(lexicon)
+    }
+
+    ///Adds a reading of the form after the readings it has already
+    pub fn insert(&mut self, form: &str, lemma: Lemma) {
+        self.lemmas
+            .insert((lemma.lemma.clone(), lemma.part_of_speech));
+        let readings = self.forms.entry(normalize(form)).or_default();
+        if !readings.contains(&lemma) {
+            readings.push(lemma);
+        }
+    }
+
+    ///The readings of the form, the most frequent first
+    pub fn get(&self, form: &str) -> &[Lemma] {
+        self.forms
+            .get(&normalize(form))
+            .map_or(&[], |readings| readings.as_slice())
+    }
+
+    ///True if the lexicon has a form with this lemma and part of speech
+    pub fn has_lemma(&self, lemma: &str, part_of_speech: PartOfSpeech) -> bool {
+        self.lemmas.contains(&(String::from(lemma), part_of_speech))
+    }
+
+    ///Number of forms
+    pub fn len(&self) -> usize {
+        self.forms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.forms.is_empty()
+    }
+}
+
+///Replaces an inflectional ending by the ending of the lemma, e.g. "aba" by "ar" in "tomaba"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SuffixRule {
+    pub suffix: String,
+    pub replacement: String,
+    pub part_of_speech: PartOfSpeech,
+    ///true if the ending is distinctive enough to use the rule for words the lexicon doesn't know.
+    ///Other rules only apply if the lexicon has the resulting lemma.
+    pub reliable: bool,
+}
+
+impl SuffixRule {
+    pub fn new(
+        suffix: &str,
+        replacement: &str,
+        part_of_speech: PartOfSpeech,
+        reliable: bool,
+    ) -> SuffixRule {
+        SuffixRule {
+            suffix: String::from(suffix),
+            replacement: String::from(replacement),
+            part_of_speech,
+            reliable,
+        }
+    }
+
+    ///The rules of the language. There are only rules for Spanish so far, other languages rely on their lexicon.
+    pub fn for_language(language: Language) -> Vec<SuffixRule> {
+        match language {
+            Language::Spanish => spanish_rules(),
+            Language::English | Language::German => vec![],
+        }
+    }
+
+    ///The lemma the rule makes of the word, if the word has the suffix and a stem of at least two letters
+    fn apply(&self, word: &str) -> Option<String> {
+        let stem = word.strip_suffix(self.suffix.as_str())?;
+        if stem.chars().count() < 2 {
+            return None;
+        }
+        Some(format!("{}{}", stem, self.replacement))
+    }
+}
+
+fn spanish_rules() -> Vec<SuffixRule> {
+    use PartOfSpeech::{Adjective, Adverb, Noun, Verb};
+    //(endings, replacement, part of speech, reliable)
+    let rules: [(&str, &str, PartOfSpeech, bool); 44] = [
+        //gerunds and participles. "cuando" and "comando" aren't gerunds.
+        ("ando", "ar", Verb, false),
+        ("iendo", "er", Verb, true),
+        ("iendo", "ir", Verb, false),
+        ("ado ada ados adas", "ar", Verb, true),
+        ("ido ida idos idas", "er", Verb, false),
+        ("ido ida idos idas", "ir", Verb, false),
+        //imperfect, future and conditional
+        ("aba abas ábamos abais aban", "ar", Verb, true),
+        ("ía ías íamos íais ían", "er", Verb, false),
+        ("ía ías íamos íais ían", "ir", Verb, false),
+        (
+            "aré arás ará aremos aréis arán aría arías aríamos aríais arían",
+            "ar",
+            Verb,
+            true,
+        ),
+        ("eré erás erá eremos eréis erán", "er", Verb, true),
+        //"ería" and "erías" are the endings of nouns like "cafetería" too
+        ("ería erías", "er", Verb, false),
+        ("eríamos eríais erían", "er", Verb, true),
+        (
+            "iré irás irá iremos iréis irán iría irías iríamos iríais irían",
+            "ir",
+            Verb,
+            true,
+        ),
+        //preterite and imperfect subjunctive
+        ("aste asteis aron", "ar", Verb, true),
+        ("ó", "ar", Verb, true),
+        //"café" and "bebé" aren't verbs
+        ("é", "ar", Verb, false),
+        ("ieron ió", "er", Verb, true),
+        ("ieron ió iste isteis í", "ir", Verb, false),
+        ("iste isteis í", "er", Verb, false),
+        ("ara aras áramos arais aran", "ar", Verb, false),
+        ("iera ieras iéramos ierais ieran", "er", Verb, false),
+        ("iera ieras iéramos ierais ieran", "ir", Verb, false),
+        //present indicative and subjunctive
+        ("amos áis", "ar", Verb, true),
+        ("emos éis", "er", Verb, true),
+        //"últimos" and "primos" aren't verbs
+        ("imos", "ir", Verb, false),
+        ("o as a an", "ar", Verb, false),
+        ("o es e en", "er", Verb, false),
+        ("o es e ís en", "ir", Verb, false),
+        ("e es emos éis en", "ar", Verb, false),
+        ("a as amos áis an", "er", Verb, false),
+        ("a as amos áis an", "ir", Verb, false),
+        //plurals of nouns and adjectives
+        ("ones", "ón", Noun, true),
+        ("enes", "én", Noun, false),
+        ("dades", "dad", Noun, true),
+        ("ores", "or", Noun, true),
+        ("ales", "al", Adjective, true),
+        ("eles", "el", Noun, true),
+        ("iles", "il", Noun, true),
+        ("ces", "z", Noun, false),
+        ("s es", "", Noun, false),
+        ("s es", "", Adjective, false),
+        ("a as os", "o", Adjective, false),
+        ("mente", "mente", Adverb, true),
+    ];
+    let mut expanded = Vec::new();
+    for (endings, replacement, part_of_speech, reliable) in rules.iter() {
+        for ending in endings.split(' ') {
+            expanded.push(SuffixRule::new(
+                ending,
+                replacement,
+                *part_of_speech,
+                *reliable,
+            ));
+        }
+    }
+    //nouns ending in an unstressed vowel, which the lexicon doesn't know: "coches".
+    //Words like "atrás" or "país" aren't plurals.
+    for (ending, replacement) in [("as", "a"), ("es", "e"), ("os", "o")] {
+        expanded.push(SuffixRule::new(ending, replacement, Noun, true));
+    }
+    expanded
+}
+
+///Finds the lemmas of words by looking them up in a lexicon and by removing inflectional endings
+pub struct Lemmatizer {
+    pub language: Language,
+    pub lexicon: Lexicon,
+    ///rules sorted by the length of their suffix, the longest first
+    pub rules: Vec<SuffixRule>,
+}
+
+impl Lemmatizer {
+    pub fn new(language: Language, lexicon: Lexicon) -> Lemmatizer {
+        let mut rules = SuffixRule::for_language(language);
+        rules.sort_by_key(|rule| std::cmp::Reverse(rule.suffix.chars().count()));
+        Lemmatizer {
+            language,
+            lexicon,
+            rules,
+        }
+    }
+
+    ///Lemmatizer with the Spanish lexicon shipped with the repository
+    pub fn spanish() -> Result<Lemmatizer, LexiconError> {
+        Ok(Lemmatizer::new(
+            Language::Spanish,
+            Lexicon::load(SPANISH_LEXICON)?,
+        ))
+    }
+
+    ///Lemmatizer with the lexicon shipped for the language, or with an empty lexicon if there is none
+    pub fn for_language(language: Language) -> Result<Lemmatizer, LexiconError> {
+        match language {
+            Language::Spanish => Lemmatizer::spanish(),
+            Language::English | Language::German => Ok(Lemmatizer::new(language, Lexicon::new())),
+        }
+    }
+
+    ///The lemma of a word. The lexicon is looked up first, then the rules whose result the lexicon knows,
+    ///then the reliable rule with the longest suffix. Words no rule applies to are their own lemma with an undefined part of speech.
+    pub fn lemmatize(&self, word: &str) -> Lemma {
+        let word = normalize(word);
+        if let Some(lemma) = self.lexicon.get(&word).first() {
+            return lemma.clone();
+        }
+        let candidates: Vec<(&SuffixRule, String)> = self
+            .rules
+            .iter()
+            .filter_map(|rule| rule.apply(&word).map(|lemma| (rule, lemma)))
+            .collect();
+        //a form like "casas" can be a plural and a verb form, the nominal reading is the more common one
+        let known = candidates
+            .iter()
+            .filter(|(rule, lemma)| self.lexicon.has_lemma(lemma, rule.part_of_speech))
+            .min_by_key(|(rule, _)| rule.part_of_speech == PartOfSpeech::Verb);
+        match known.or_else(|| candidates.iter().find(|(rule, _)| rule.reliable)) {
+            Some((rule, lemma)) => Lemma::new(lemma, rule.part_of_speech),
+            None => Lemma::new(&word, PartOfSpeech::Undefined),
+        }
+    }
+
+    ///The lemmas of a word token, one for each part of a contraction or a verb with clitics.
+    ///Numbers and punctuation have no lemma.
+    pub fn lemmatize_token(&self, token: &Token) -> Vec<Lemma> {
+        if token.kind != TokenKind::Word {
+            return vec![];
+        }
+        if token.parts.is_empty() {
+            return vec![self.lemmatize(&token.normalized)];
+        }
+        token
+            .parts
+            .iter()
+            .map(|part| self.lemmatize(part))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::words::Tokenizer;
+
+    fn lemma(lemmatizer: &Lemmatizer, word: &str) -> (String, PartOfSpeech) {
+        let lemma = lemmatizer.lemmatize(word);
+        (lemma.lemma, lemma.part_of_speech)
+    }
+
+    #[test]
+    fn test_lexicon() {
+        let lexicon =
+            Lexicon::parse("# comment\ntomé\ttomar\tVERB\r\nla\tel\tDET\nla\tél\tPRON\n").unwrap();
+        assert_eq!(
+            lexicon.get("Tomé"),
+            &[Lemma::new("tomar", PartOfSpeech::Verb)]
+        );
+        assert_eq!(lexicon.get("la").len(), 2);
+        assert!(lexicon.has_lemma("él", PartOfSpeech::Pronoun));
+        assert!(matches!(
+            Lexicon::parse("tomé\ttomar\tVERBO"),
+            Err(LexiconError::InvalidLine { line: 1, .. })
+        ));
+        assert!(Lexicon::load(SPANISH_LEXICON).unwrap().len() > 1000);
+    }
+
+    #[test]
+    fn test_spanish() {
+        let lemmatizer = Lemmatizer::spanish().unwrap();
+        let verb = |lemma: &str| (String::from(lemma), PartOfSpeech::Verb);
+        //irregular forms from the lexicon
+        assert_eq!(lemma(&lemmatizer, "fueron"), verb("ser"));
+        assert_eq!(lemma(&lemmatizer, "Tuvo"), verb("tener"));
+        //rules checked against the lexicon
+        assert_eq!(lemma(&lemmatizer, "tomé"), verb("tomar"));
+        assert_eq!(lemma(&lemmatizer, "comía"), verb("comer"));
+        assert_eq!(lemma(&lemmatizer, "vivía"), verb("vivir"));
+        assert_eq!(
+            lemma(&lemmatizer, "casas"),
+            (String::from("casa"), PartOfSpeech::Noun)
+        );
+        assert_eq!(
+            lemma(&lemmatizer, "buenas"),
+            (String::from("bueno"), PartOfSpeech::Adjective)
+        );
+        //reliable rules for words the lexicon doesn't know
+        assert_eq!(lemma(&lemmatizer, "cocinaba"), verb("cocinar"));
+        assert_eq!(
+            lemma(&lemmatizer, "canciones"),
+            (String::from("canción"), PartOfSpeech::Noun)
+        );
+        assert_eq!(
+            lemma(&lemmatizer, "rehenes"),
+            (String::from("rehén"), PartOfSpeech::Noun)
+        );
+        assert_eq!(lemma(&lemmatizer, "tonterías").0, "tontería");
+        assert_eq!(lemma(&lemmatizer, "comenzamos").0, "comenzar");
+        assert_eq!(
+            lemma(&lemmatizer, "Nairobi"),
+            (String::from("nairobi"), PartOfSpeech::Undefined)
+        );
+        //unreliable rules for words the lexicon knows the verb of
+        assert_eq!(lemma(&lemmatizer, "trabajando"), verb("trabajar"));
+        assert_eq!(lemma(&lemmatizer, "vivimos"), verb("vivir"));
+    }
+
+    #[test]
+    fn test_unknown_words() {
+        let lemmatizer = Lemmatizer::new(Language::Spanish, Lexicon::new());
+        assert_eq!(
+            lemma(&lemmatizer, "cuando"),
+            (String::from("cuando"), PartOfSpeech::Undefined)
+        );
+        assert_eq!(
+            lemma(&lemmatizer, "comando"),
+            (String::from("comando"), PartOfSpeech::Undefined)
+        );
+        assert_eq!(
+            lemma(&lemmatizer, "últimos"),
+            (String::from("último"), PartOfSpeech::Noun)
+        );
+        assert_eq!(
+            lemma(&lemmatizer, "primos"),
+            (String::from("primo"), PartOfSpeech::Noun)
+        );
+        assert_eq!(
+            lemma(&lemmatizer, "cocinaba"),
+            (String::from("cocinar"), PartOfSpeech::Verb)
+        );
+    }
+
+    #[test]
+    fn test_tokens() {
+        let lemmatizer = Lemmatizer::spanish().unwrap();
+        let tokens = Tokenizer::new(Language::Spanish).tokenize("Tómalo del coche, 3 veces.");
+        let lemmas: Vec<Vec<String>> = tokens
+            .iter()
+            .map(|token| {
+                lemmatizer
+                    .lemmatize_token(token)
+                    .into_iter()
+                    .map(|l| l.lemma)
+                    .collect()
+            })
+            .collect();
+        let expected: Vec<Vec<&str>> = vec![
+            vec!["tomar", "él"],
+            vec!["de", "el"],
+            vec!["coche"],
+            vec![],
+            vec![],
+            vec!["vez"],
+            vec![],
+        ];
+        assert_eq!(lemmas, expected);
+    }
+}
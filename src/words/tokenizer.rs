@@ -0,0 +1,392 @@
+use crate::flashcard::Language;
+
+const APOSTROPHES: [char; 3] = ['\'', '’', 'ʼ'];
+//the longer ones first, so "vámonos" ends with "nos" and not "os"
+const SPANISH_CLITICS: [&str; 11] = [
+    "nos", "los", "las", "les", "me", "te", "se", "os", "lo", "la", "le",
+];
+//short imperatives which take clitics without a written accent: "dime", "hazlo"
+const SPANISH_IMPERATIVES: [&str; 9] = ["da", "di", "haz", "pon", "ten", "ven", "sal", "ve", "sé"];
+//infinitives shorter than four letters
+const SPANISH_SHORT_INFINITIVES: [&str; 6] = ["dar", "ver", "ir", "ser", "oír", "reír"];
+//plurals with a written accent which look like an imperative with a clitic: "ángeles" isn't "ange" with "les"
+const SPANISH_PLURALS_LIKE_CLITICS: [&str; 14] = [
+    "ángeles",
+    "cárceles",
+    "túneles",
+    "plátanos",
+    "órganos",
+    "huérfanos",
+    "sótanos",
+    "océanos",
+    "rábanos",
+    "escándalos",
+    "vándalos",
+    "pétalos",
+    "cánones",
+    "cráneos",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Word,
+    Number,
+    Punctuation,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    ///the token as written in the text
+    pub text: String,
+    ///lowercase with straight apostrophes, used to look the word up
+    pub normalized: String,
+    pub kind: TokenKind,
+    ///byte offset of the token in the text
+    pub start: usize,
+    ///the words a contraction or a verb with clitics is made of, e.g. "del" is "de" and "el",
+    ///"dámelo" is "da", "me" and "lo". Empty for all other tokens.
+    pub parts: Vec<String>,
+}
+
+///Splits text into words, numbers and punctuation
+pub struct Tokenizer {
+    pub language: Language,
+}
+
+impl Tokenizer {
+    pub fn new(language: Language) -> Tokenizer {
+        Tokenizer { language }
+    }
+
+    pub fn tokenize(&self, text: &str) -> Vec<Token> {
+        let chars: Vec<(usize, char)> = text.char_indices().collect();
+        let end_of = |i: usize| chars.get(i).map_or(text.len(), |(index, _)| *index);
+        let mut tokens = Vec::new();
+        let mut i = 0;
+        while i < chars.len() {
+            let (start, c) = chars[i];
+            let kind = if c.is_alphabetic() {
+                TokenKind::Word
+            } else if c.is_numeric() {
+                TokenKind::Number
+            } else if c.is_whitespace() {
+                i += 1;
+                continue;
+            } else {
+                TokenKind::Punctuation
+            };
+            i += 1;
+            match kind {
+                TokenKind::Word => {
+                    //apostrophes and hyphens join letters: "wasn’t", "e-mail"
+                    while i < chars.len() {
+                        let c = chars[i].1;
+                        let joins = (APOSTROPHES.contains(&c) || c == '-')
+                            && chars.get(i + 1).is_some_and(|(_, n)| n.is_alphabetic());
+                        if c.is_alphanumeric() || is_mark(c) {
+                            i += 1;
+                        } else if joins {
+                            i += 2;
+                        } else {
+                            break;
+                        }
+                    }
+                }
+                TokenKind::Number => {
+                    //separators between digits and ordinal letters: "9,999,518", "3.50", "8:35", "1ª"
+                    while i < chars.len() {
+                        let c = chars[i].1;
+                        let separates = ".,:".contains(c)
+                            && chars.get(i + 1).is_some_and(|(_, n)| n.is_numeric());
+                        if c.is_alphanumeric() {
+                            i += 1;
+                        } else if separates {
+                            i += 2;
+                        } else {
+                            break;
+                        }
+                    }
+                }
+                TokenKind::Punctuation => {
+                    //an ellipsis or "?!" is one token
+                    while i < chars.len() && chars[i].1 == c {
+                        i += 1;
+                    }
+                }
+            }
+            let token_text = &text[start..end_of(i)];
+            let normalized = normalize(token_text);
+            let parts = match kind {
+                TokenKind::Word => self.parts(&normalized),
+                _ => vec![],
+            };
+            tokens.push(Token {
+                text: String::from(token_text),
+                normalized,
+                kind,
+                start,
+                parts,
+            });
+        }
+        tokens
+    }
+
+    ///Splits contractions and clitics and drops the English possessive "'s".
+    ///Returns an empty vector if the word isn't made of several words.
+    pub fn parts(&self, word: &str) -> Vec<String> {
+        match self.language {
+            Language::Spanish => spanish_parts(word),
+            Language::English => english_parts(word),
+            Language::German => german_parts(word),
+        }
+    }
+}
+
+///Lowercase with straight apostrophes
+pub fn normalize(word: &str) -> String {
+    word.to_lowercase()
+        .chars()
+        .map(|c| if APOSTROPHES.contains(&c) { '\'' } else { c })
+        .collect()
+}
+
+///Combining marks which belong to the previous letter
+fn is_mark(c: char) -> bool {
+    ('\u{300}'..='\u{36f}').contains(&c)
+}
+
+fn to_strings(words: &[&str]) -> Vec<String> {
+    words.iter().map(|word| String::from(*word)).collect()
+}
+
+fn spanish_parts(word: &str) -> Vec<String> {
+    match word {
+        "del" => return to_strings(&["de", "el"]),
+        "al" => return to_strings(&["a", "el"]),
+        _ => (),
+    }
+    //adverbs like "fácilmente" aren't "facilmen" with "te"
+    if SPANISH_PLURALS_LIKE_CLITICS.contains(&word) || word.ends_with("mente") {
+        return vec![];
+    }
+    //one or two clitics at the end of infinitives, gerunds and imperatives
+    for last in SPANISH_CLITICS.iter() {
+        let stem = match word.strip_suffix(last) {
+            Some(stem) => stem,
+            None => continue,
+        };
+        for first in SPANISH_CLITICS.iter() {
+            if let Some(verb) = stem
+                .strip_suffix(first)
+                .and_then(|s| spanish_verb(s, first))
+            {
+                return [verb, String::from(*first), String::from(*last)].to_vec();
+            }
+        }
+        if let Some(verb) = spanish_verb(stem, last) {
+            return [verb, String::from(*last)].to_vec();
+        }
+    }
+    vec![]
+}
+
+///Returns the verb form without the accent the clitic added, if the stem looks like a verb which takes clitics
+fn spanish_verb(stem: &str, clitic: &str) -> Option<String> {
+    //"vámonos" is "vamos" and "nos", "sentémonos" is "sentemos" and "nos"
+    let first_person_plural =
+        clitic == "nos" && stem.ends_with("mo") && stem != remove_accents(stem);
+    let stem = if first_person_plural {
+        format!("{}s", stem)
+    } else {
+        String::from(stem)
+    };
+    let unaccented = remove_accents(&stem);
+    let chars: Vec<char> = unaccented.chars().collect();
+    //infinitives aren't written with an accent, except "oír" and "reír"
+    let infinitive = (unaccented == stem || SPANISH_SHORT_INFINITIVES.contains(&stem.as_str()))
+        && ["ar", "er", "ir"]
+            .iter()
+            .any(|ending| unaccented.ends_with(ending))
+        && (SPANISH_SHORT_INFINITIVES.contains(&stem.as_str())
+            //"muerte" and "suerte" are not "muer" with "te"
+            || (chars.len() >= 4 && !"iou".contains(chars[chars.len() - 3])));
+    let gerund = ["ando", "iendo", "yendo"]
+        .iter()
+        .any(|ending| unaccented.ends_with(ending));
+    //the clitic moves the stress, so "da" is written "dá" in "dámelo"
+    let imperative = first_person_plural
+        || SPANISH_IMPERATIVES.contains(&stem.as_str())
+        || SPANISH_IMPERATIVES.contains(&unaccented.as_str())
+        || (unaccented != stem && unaccented.ends_with(['a', 'e', 'n']) && chars.len() >= 3);
+    //too many nouns end in "os" like "dios" or "primeros" to take it for a clitic after anything but a gerund
+    if clitic == "os" && !gerund {
+        return None;
+    }
+    if !(infinitive || gerund || imperative) {
+        return None;
+    }
+    //the accent is only dropped where the clitic caused it
+    if infinitive || SPANISH_IMPERATIVES.contains(&stem.as_str()) {
+        Some(stem)
+    } else {
+        Some(unaccented)
+    }
+}
+
+fn remove_accents(word: &str) -> String {
+    word.chars()
+        .map(|c| match c {
+            'á' => 'a',
+            'é' => 'e',
+            'í' => 'i',
+            'ó' => 'o',
+            'ú' => 'u',
+            _ => c,
+        })
+        .collect()
+}
+
+fn english_parts(word: &str) -> Vec<String> {
+    let irregular = match word {
+        "won't" => Some(["will", "not"]),
+        "can't" => Some(["can", "not"]),
+        "shan't" => Some(["shall", "not"]),
+        "ain't" => Some(["is", "not"]),
+        "let's" => Some(["let", "us"]),
+        _ => None,
+    };
+    if let Some(parts) = irregular {
+        return to_strings(&parts);
+    }
+    let (base, suffix) = match word.rfind('\'') {
+        Some(i) => (&word[..i], &word[i..]),
+        None => return vec![],
+    };
+    let (base, expansion) = match suffix {
+        "'ll" => (base, "will"),
+        "'re" => (base, "are"),
+        "'ve" => (base, "have"),
+        "'d" => (base, "would"),
+        "'m" => (base, "am"),
+        "'t" if base.ends_with('n') => (&base[..base.len() - 1], "not"),
+        "'s" if [
+            "it", "he", "she", "that", "what", "there", "here", "who", "where",
+        ]
+        .contains(&base) =>
+        {
+            (base, "is")
+        }
+        //the possessive isn't a word of its own, so only the owner is kept
+        "'s" => return to_strings(&[base]),
+        _ => return vec![],
+    };
+    to_strings(&[base, expansion])
+}
+
+fn german_parts(word: &str) -> Vec<String> {
+    let parts = match word {
+        "zum" => ["zu", "dem"],
+        "zur" => ["zu", "der"],
+        "im" => ["in", "dem"],
+        "ins" => ["in", "das"],
+        "am" => ["an", "dem"],
+        "ans" => ["an", "das"],
+        "vom" => ["von", "dem"],
+        "beim" => ["bei", "dem"],
+        _ => return vec![],
+    };
+    to_strings(&parts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(language: Language, text: &str) -> Vec<(String, Vec<String>)> {
+        Tokenizer::new(language)
+            .tokenize(text)
+            .into_iter()
+            .filter(|t| t.kind == TokenKind::Word)
+            .map(|t| (t.normalized, t.parts))
+            .collect()
+    }
+
+    fn parts(language: Language, word: &str) -> Vec<String> {
+        Tokenizer::new(language).parts(word)
+    }
+
+    #[test]
+    fn test_tokens() {
+        let tokens = Tokenizer::new(Language::Spanish)
+            .tokenize("¿Cuesta 3.50 euros... o 9,999,518? ¡E-mail!");
+        let texts: Vec<(&str, TokenKind)> =
+            tokens.iter().map(|t| (t.text.as_str(), t.kind)).collect();
+        assert_eq!(
+            texts,
+            vec![
+                ("¿", TokenKind::Punctuation),
+                ("Cuesta", TokenKind::Word),
+                ("3.50", TokenKind::Number),
+                ("euros", TokenKind::Word),
+                ("...", TokenKind::Punctuation),
+                ("o", TokenKind::Word),
+                ("9,999,518", TokenKind::Number),
+                ("?", TokenKind::Punctuation),
+                ("¡", TokenKind::Punctuation),
+                ("E-mail", TokenKind::Word),
+                ("!", TokenKind::Punctuation),
+            ]
+        );
+        assert_eq!(tokens[1].start, 2);
+        assert_eq!(tokens[9].normalized, "e-mail");
+    }
+
+    #[test]
+    fn test_spanish_parts() {
+        assert_eq!(parts(Language::Spanish, "del"), vec!["de", "el"]);
+        assert_eq!(parts(Language::Spanish, "al"), vec!["a", "el"]);
+        assert_eq!(parts(Language::Spanish, "dámelo"), vec!["da", "me", "lo"]);
+        assert_eq!(parts(Language::Spanish, "dime"), vec!["di", "me"]);
+        assert_eq!(parts(Language::Spanish, "cállate"), vec!["calla", "te"]);
+        assert_eq!(parts(Language::Spanish, "vámonos"), vec!["vamos", "nos"]);
+        assert_eq!(parts(Language::Spanish, "hablarle"), vec!["hablar", "le"]);
+        assert_eq!(
+            parts(Language::Spanish, "diciéndoselo"),
+            vec!["diciendo", "se", "lo"]
+        );
+        assert_eq!(parts(Language::Spanish, "verte"), vec!["ver", "te"]);
+        for word in [
+            "parte",
+            "muerte",
+            "tomate",
+            "carlos",
+            "noche",
+            "árboles",
+            "dios",
+            "números",
+            "primeros",
+            "ángeles",
+            "cárceles",
+            "plátanos",
+            "fácilmente",
+        ] {
+            assert!(parts(Language::Spanish, word).is_empty(), "{}", word);
+        }
+    }
+
+    #[test]
+    fn test_english_and_german_parts() {
+        assert_eq!(
+            words(Language::English, "I’ll go, it wasn’t Tokyo's."),
+            vec![
+                (String::from("i'll"), to_strings(&["i", "will"])),
+                (String::from("go"), vec![]),
+                (String::from("it"), vec![]),
+                (String::from("wasn't"), to_strings(&["was", "not"])),
+                (String::from("tokyo's"), to_strings(&["tokyo"])),
+            ]
+        );
+        assert_eq!(parts(Language::English, "won't"), vec!["will", "not"]);
+        assert_eq!(parts(Language::German, "zum"), vec!["zu", "dem"]);
+    }
+}